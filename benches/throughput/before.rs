@@ -0,0 +1,390 @@
+// The lexer as it was before it worked on bytes, frozen here so the bench
+// has something to measure the gain against. It decodes every character,
+// steps the automaton one character at a time, advances the cursor the
+// same way, and tries every comment form at every token. Only what the
+// bench uses is kept: default comments and keywords, always recovering,
+// and no tracing (whose bookkeeping per step made it a little slower
+// still). Don't fix or speed it up.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+use unicode_xid::UnicodeXID;
+
+use cpsc323_lexer::dfa::{Dfa, StateId};
+use cpsc323_lexer::table::{self, Accept, Class, States};
+use cpsc323_lexer::{CommentSyntax, LexerError, Literal, Separator, Span, Token, TokenType, DEFAULT_KEYWORDS};
+
+// Walks the source so we always know where we are in it
+struct Cursor<'a> {
+    src: &'a str,
+    offset: usize,
+    line: usize,
+    col: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(src: &'a str) -> Self {
+        Cursor {
+            src,
+            offset: 0,
+            line: 1,
+            col: 1,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.offset..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+        Some(c)
+    }
+
+    fn here(&self) -> Span {
+        Span {
+            start: self.offset,
+            end: self.offset,
+            line: self.line,
+            col: self.col,
+        }
+    }
+
+    fn to(&self, start: Span) -> Span {
+        Span {
+            end: self.offset,
+            ..start
+        }
+    }
+
+    fn slice(&self, span: Span) -> &'a str {
+        &self.src[span.start..span.end]
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.offset..]
+    }
+
+    fn advance(&mut self, len: usize) {
+        let end = self.offset + len;
+        while self.offset < end {
+            self.next();
+        }
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        if !self.rest().starts_with(s) {
+            return false;
+        }
+        self.advance(s.len());
+        true
+    }
+}
+
+fn comment_starting_at<'a>(chas: &Cursor, syntaxes: &'a [CommentSyntax]) -> Option<&'a CommentSyntax> {
+    let rest = chas.rest();
+    syntaxes.iter().find(|s| rest.starts_with(s.open()))
+}
+
+fn skip_comment(chas: &mut Cursor, syn: &CommentSyntax) -> Result<Span, LexerError> {
+    let start = chas.here();
+    chas.eat(syn.open());
+    let opener = chas.to(start);
+    let close = match syn.close() {
+        Some(close) => close,
+        None => {
+            while !matches!(chas.peek(), None | Some('\n')) {
+                chas.next();
+            }
+            return Ok(chas.to(start));
+        }
+    };
+    loop {
+        if chas.eat(close) {
+            return Ok(chas.to(start));
+        } else if chas.next().is_none() {
+            return Err(LexerError::UnterminatedComment(opener));
+        }
+    }
+}
+
+fn classify(dfa: &Dfa<Accept>, c: char, unicode: bool) -> u8 {
+    if unicode && !c.is_ascii() {
+        if c.is_xid_start() {
+            return Class::Letter as u8;
+        }
+        if c.is_xid_continue() {
+            return Class::UnicodeContinue as u8;
+        }
+    }
+    dfa.class_of(c)
+}
+
+fn is_sync(c: char) -> bool {
+    matches!(
+        c,
+        ' ' | '\t' | '\r' | '\n'
+            | '(' | ')' | '{' | '}' | '[' | ']' | ',' | ':' | ';' | '#' | '$'
+            | '+' | '-' | '*' | '/' | '<' | '>' | '=' | '"'
+    )
+}
+
+pub struct Lexer<'src> {
+    chas: Cursor<'src>,
+    start: Span,
+    comments: Vec<CommentSyntax>,
+    keywords: HashSet<String>,
+    unicode: bool,
+    prev: Option<TokenType>,
+}
+
+impl<'src> Lexer<'src> {
+    pub fn new(src: &'src str, unicode: bool) -> Self {
+        let chas = Cursor::new(src);
+        Lexer {
+            start: chas.here(),
+            chas,
+            comments: CommentSyntax::defaults(),
+            keywords: DEFAULT_KEYWORDS.iter().map(|k| k.to_string()).collect(),
+            unicode,
+            prev: None,
+        }
+    }
+
+    fn resync(&mut self) -> Token<'src> {
+        while let Some(c) = self.chas.peek() {
+            if is_sync(c) {
+                break;
+            }
+            self.chas.next();
+        }
+        if self.chas.offset == self.start.start {
+            self.chas.next();
+        }
+        let span = self.chas.to(self.start);
+        Token {
+            ty: TokenType::Error,
+            lex: Cow::Borrowed(self.chas.slice(span)),
+            span,
+            value: None,
+        }
+    }
+
+    fn lex(&mut self) -> Option<Result<Token<'src>, LexerError>> {
+        let dfa = table::builtin();
+        loop {
+            let start = self.chas.here();
+            self.start = start;
+            let c = self.chas.peek()?;
+            if let Some(syn) = comment_starting_at(&self.chas, &self.comments) {
+                if let Err(e) = skip_comment(&mut self.chas, syn) {
+                    return Some(Err(e));
+                }
+                continue;
+            }
+            if c == '"' {
+                return Some(string(&mut self.chas, start));
+            }
+            let state = match self.prev {
+                Some(
+                    TokenType::Identifier
+                    | TokenType::Separator(Separator::RParen | Separator::RBracket | Separator::RBrace),
+                ) => States::StartMember,
+                _ => States::Start,
+            };
+            let unicode = self.unicode;
+            let m = dfa.longest_match(state as StateId, self.chas.rest(), |c| classify(dfa, c, unicode));
+            let (acc, len) = match m {
+                Some((acc, len)) if len > 0 => (acc, len - acc.trailing()),
+                _ => {
+                    self.chas.next();
+                    return Some(Err(LexerError::InternalStateError));
+                }
+            };
+            self.chas.advance(len);
+            if acc == Accept::Whitespace {
+                continue;
+            }
+            let span = self.chas.to(start);
+            return Some(basic(acc, self.chas.slice(span), span, &self.keywords));
+        }
+    }
+}
+
+impl<'src> Iterator for Lexer<'src> {
+    type Item = Result<Token<'src>, LexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let tok = match self.lex()? {
+            Ok(tok) => tok,
+            Err(_) => self.resync(),
+        };
+        self.prev = Some(tok.ty);
+        Some(Ok(tok))
+    }
+}
+
+fn string<'src>(chas: &mut Cursor<'src>, start: Span) -> Result<Token<'src>, LexerError> {
+    chas.next();
+    let quote = chas.to(start);
+    let body = chas.here();
+    let mut value: Option<String> = None;
+    let mut bad = None;
+    loop {
+        let at = chas.here();
+        match chas.peek() {
+            None | Some('\n') => return Err(LexerError::UnterminatedString(quote)),
+            Some('"') => {
+                chas.next();
+                break;
+            }
+            Some('\\') => {
+                let value = value.get_or_insert_with(|| chas.slice(chas.to(body)).to_string());
+                chas.next();
+                match escape(chas, at) {
+                    Ok(c) => value.push(c),
+                    Err(e) => {
+                        bad.get_or_insert(e);
+                    }
+                }
+            }
+            Some(c) => {
+                chas.next();
+                if let Some(value) = &mut value {
+                    value.push(c);
+                }
+            }
+        }
+    }
+    if let Some(e) = bad {
+        return Err(e);
+    }
+    let span = chas.to(start);
+    let lex = chas.slice(span);
+    let value = match value {
+        Some(value) => Cow::Owned(value),
+        None => Cow::Borrowed(&lex[1..lex.len() - 1]),
+    };
+    Ok(Token {
+        ty: TokenType::StringLiteral,
+        lex: Cow::Borrowed(lex),
+        span,
+        value: Some(Literal::Str(value)),
+    })
+}
+
+fn escape(chas: &mut Cursor, at: Span) -> Result<char, LexerError> {
+    let c = match chas.peek() {
+        Some('\n') | None => return Err(LexerError::InvalidEscape(chas.to(at))),
+        Some(c) => c,
+    };
+    chas.next();
+    let code = match c {
+        'n' => return Ok('\n'),
+        't' => return Ok('\t'),
+        '\\' => return Ok('\\'),
+        '"' => return Ok('"'),
+        'x' => hex_digits(chas, 2, 2),
+        'u' if chas.peek() == Some('{') => {
+            chas.next();
+            let code = hex_digits(chas, 1, 6);
+            if chas.peek() == Some('}') {
+                chas.next();
+                code
+            } else {
+                None
+            }
+        }
+        _ => None,
+    };
+    code.and_then(char::from_u32)
+        .ok_or_else(|| LexerError::InvalidEscape(chas.to(at)))
+}
+
+fn hex_digits(chas: &mut Cursor, min: usize, max: usize) -> Option<u32> {
+    let mut code = 0;
+    let mut n = 0;
+    while n < max {
+        match chas.peek().and_then(|c| c.to_digit(16)) {
+            Some(d) => {
+                code = code * 16 + d;
+                n += 1;
+                chas.next();
+            }
+            None => break,
+        }
+    }
+    (n >= min).then_some(code)
+}
+
+fn basic<'src>(
+    acc: Accept,
+    lexeme: &'src str,
+    span: Span,
+    keywords: &HashSet<String>,
+) -> Result<Token<'src>, LexerError> {
+    let ty = match acc {
+        Accept::Identifier => {
+            let lexeme: Cow<str> = match is_nfc_quick(lexeme.chars()) {
+                IsNormalized::Yes => Cow::Borrowed(lexeme),
+                _ => Cow::Owned(lexeme.nfc().collect()),
+            };
+            return Ok(Token {
+                ty: match keywords.contains(&*lexeme) {
+                    true => TokenType::Keyword,
+                    false => TokenType::Identifier,
+                },
+                lex: lexeme,
+                span,
+                value: None,
+            });
+        }
+        Accept::Integer(radix) | Accept::IntegerBeforeRange(radix) => {
+            let digits = match radix.value() {
+                10 => lexeme,
+                _ => &lexeme[2..],
+            };
+            let n = u64::from_str_radix(&digits.replace('_', ""), radix.value())
+                .map_err(|_| LexerError::IntegerOverflow(span))?;
+            return Ok(Token {
+                ty: TokenType::Number,
+                lex: Cow::Borrowed(lexeme),
+                span,
+                value: Some(Literal::Int { value: n, radix }),
+            });
+        }
+        Accept::Real => {
+            let x = lexeme
+                .replace('_', "")
+                .parse::<f64>()
+                .ok()
+                .filter(|x| x.is_finite())
+                .ok_or(LexerError::InvalidReal(span))?;
+            return Ok(Token {
+                ty: TokenType::Real,
+                lex: Cow::Borrowed(lexeme),
+                span,
+                value: Some(Literal::Real(x)),
+            });
+        }
+        Accept::Operator(op) => TokenType::Operator(op),
+        Accept::Separator(sep) => TokenType::Separator(sep),
+        // The bench's inputs have no errors, so which one doesn't matter
+        _ => return Err(LexerError::InternalStateError),
+    };
+    Ok(Token {
+        ty,
+        lex: Cow::Borrowed(lexeme),
+        span,
+        value: None,
+    })
+}
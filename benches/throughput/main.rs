@@ -0,0 +1,118 @@
+// Lexer throughput on a few multi-megabyte inputs. Tokens are dropped as
+// they come, so this doesn't measure the allocator collecting them. Run with
+// `cargo bench --bench throughput`; it prints MB/s for each, best of a
+// handful of runs, and the gain from working on bytes: the built-in lexer
+// against a frozen copy of it from before it did (in `before.rs`), and the
+// bare automaton on bytes against on characters.
+
+mod before;
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use cpsc323_lexer::dfa::StateId;
+use cpsc323_lexer::spec::Spec;
+use cpsc323_lexer::table::{self, States};
+use cpsc323_lexer::Lexer;
+
+const SIZE: usize = 4 << 20;
+const RUNS: usize = 5;
+
+// Something like the course language: indented statements, comments,
+// strings and numbers
+fn program() -> String {
+    let lines = [
+        "function gcd(integer a, integer b)",
+        "{",
+        "    // Euclid, the slow way",
+        "    while (a <> b)",
+        "        if (a > b) a = a - b; else b = b - a; endif",
+        "    return a;",
+        "}",
+        "[* compute the running average of everything read so far *]",
+        "real average = 0.0, total = 1_000.5e-3;",
+        "integer counter = 0x1F + 0o17 + 0b1010;",
+        "put(\"average so far: \", average, \"\\n\");",
+        "for (counter = 0; counter < 100; counter = counter + 1) get(value);",
+    ];
+    repeat(&lines.join("\n"))
+}
+
+// Long names and wide indentation, where per-character costs dominate
+fn identifiers() -> String {
+    let line = "            first_long_identifier_name = second_long_identifier_name + third_name_here;";
+    repeat(line)
+}
+
+fn unicode() -> String {
+    repeat("    größe = länge * breite; // überschlag\n    ñandú = año + 1;")
+}
+
+fn repeat(text: &str) -> String {
+    let mut out = String::with_capacity(SIZE + text.len());
+    while out.len() < SIZE {
+        out.push_str(text);
+        out.push('\n');
+    }
+    out
+}
+
+// Best time of a few runs of each of `fs`, taking turns so they all see
+// the same machine
+fn time<const N: usize>(mut fs: [&mut dyn FnMut(); N]) -> [Duration; N] {
+    let mut best = [Duration::MAX; N];
+    for _ in 0..RUNS {
+        for (f, best) in fs.iter_mut().zip(&mut best) {
+            let start = Instant::now();
+            f();
+            *best = start.elapsed().min(*best);
+        }
+    }
+    best
+}
+
+fn report(name: &str, input: &str, t: Duration) {
+    let mbs = input.len() as f64 / t.as_secs_f64() / (1 << 20) as f64;
+    println!("{:<24} {:>8.1} MB/s", name, mbs);
+}
+
+// Run the built-in automaton over the whole input, one match after another
+fn scan(input: &str, mut longest_match: impl FnMut(&str) -> Option<usize>) {
+    let mut i = 0;
+    while i < input.len() {
+        // Skip a character when nothing matches, like the lexer does
+        let len = longest_match(&input[i..]).filter(|&len| len > 0);
+        i += len.unwrap_or_else(|| input[i..].chars().next().unwrap().len_utf8());
+    }
+}
+
+fn main() {
+    let spec = Spec::parse(include_str!("../../course.spec")).unwrap();
+    for (name, input) in [("program", program()), ("identifiers", identifiers()), ("unicode", unicode())] {
+        let unicode = name == "unicode";
+        let lexer = || Lexer::new(&input).unicode_identifiers(unicode).recovering();
+        let old = || before::Lexer::new(&input, unicode);
+        // Only worth timing if both lex the same
+        assert!(lexer().eq(old()), "{} lexes differently than before", name);
+        let [before, builtin, spec] = time([
+            &mut || old().for_each(|tok| drop(black_box(tok))),
+            &mut || lexer().for_each(|tok| drop(black_box(tok))),
+            &mut || spec.lexer(&input).for_each(|tok| drop(black_box(tok))),
+        ]);
+        report(&format!("before/{}", name), &input, before);
+        report(&format!("builtin/{}", name), &input, builtin);
+        println!("{:<24} {:>8.2}x", "  lexer, now/before", before.as_secs_f64() / builtin.as_secs_f64());
+        report(&format!("spec/{}", name), &input, spec);
+
+        let dfa = table::builtin();
+        let start = States::Start as StateId;
+        let class_of = |c| dfa.class_of(c);
+        let [chars, bytes] = time([
+            &mut || scan(&input, |s| dfa.longest_match(start, s, class_of).map(|m| m.1)),
+            &mut || scan(&input, |s| dfa.longest_match_bytes(start, s, class_of).map(|m| m.1)),
+        ]);
+        report(&format!("dfa-chars/{}", name), &input, chars);
+        report(&format!("dfa-bytes/{}", name), &input, bytes);
+        println!("{:<24} {:>8.2}x", "  dfa, bytes/chars", chars.as_secs_f64() / bytes.as_secs_f64());
+    }
+}
@@ -0,0 +1,23 @@
+# The course language's tokens, as a spec for `cpsc323-lexer --spec`.
+# Keywords come before identifiers so they win ties; otherwise the longest
+# match wins.
+
+digit       [0-9]
+letter      [A-Za-z_]
+blank       [ \t\r\n]
+
+%%
+
+boolean|else|endif|false|for|function|get|if|integer|put|real|return|true|while  emit Keyword
+{letter}({letter}|{digit})*      emit Identifier
+{digit}+\.{digit}+               emit Real
+{digit}+                         emit Number
+"<="|">="|"=="|"!="|"=>"|".."    emit Operator
+[-+*/<>=.]                       emit Operator
+"$$"|[(){}\[\],:;\#]             emit Separator
+\"[^"\n]*\"                      emit StringLiteral
+{blank}+                         skip
+"//"[^\n]*                       skip
+"/*"([^*]|"*"+[^*/])*"*"+"/"     skip
+"[*"([^*]|"*"+[^*\]])*"*"+"]"    skip
+.                                error illegal character
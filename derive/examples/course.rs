@@ -0,0 +1,56 @@
+// The course language's tokens as a derived lexer, with the same rules as
+// course.spec. For the course language it prints the same table as the
+// main binary, though it has none of the built-in lexer's extensions
+// (radix prefixes, exponents, string escapes, Unicode identifiers):
+// `cargo run -p cpsc323-lexer-derive --example course [FILE]`
+use cpsc323_lexer::derive::TokenKind;
+use cpsc323_lexer_derive::Lexer;
+
+#[derive(Lexer, Debug, Clone, Copy, PartialEq)]
+#[skip("[ \t\r\n]+")]
+#[skip("//[^\n]*")]
+#[skip(r#""/*"([^*]|"*"+[^*/])*"*"+"/""#)]
+#[skip(r#""[*"([^*]|"*"+[^*\]])*"*"+"]""#)]
+enum Course {
+    #[token("boolean")]
+    #[token("else")]
+    #[token("endif")]
+    #[token("false")]
+    #[token("for")]
+    #[token("function")]
+    #[token("get")]
+    #[token("if")]
+    #[token("integer")]
+    #[token("put")]
+    #[token("real")]
+    #[token("return")]
+    #[token("true")]
+    #[token("while")]
+    Keyword,
+    #[regex("[A-Za-z_][A-Za-z0-9_]*")]
+    Identifier,
+    #[regex(r"[0-9]+\.[0-9]+")]
+    Real,
+    #[regex("[0-9]+")]
+    Number,
+    #[regex(r"[-+*/<>=.]|<=|>=|==|!=|=>|\.\.")]
+    Operator,
+    #[regex(r"[(){}\[\],:;#]|\$\$")]
+    Separator,
+    #[regex(r#"\"[^"\n]*\""#)]
+    StringLiteral,
+}
+
+fn main() {
+    let src = std::env::args()
+        .nth(1)
+        .map(|f| std::fs::read_to_string(f).expect("can't read input"))
+        .unwrap_or_else(|| String::from("while (t < upper) s = 22.00;"));
+    let (toks, errors) = Course::lexer(&src).tokenize();
+    for tok in toks {
+        println!("{:>10} = {}", format!("{:?}", tok.ty), tok.lex);
+    }
+    for e in errors {
+        eprintln!("{}", e);
+    }
+}
@@ -0,0 +1,136 @@
+//! `#[derive(Lexer)]`: a DFA lexer for a token kind enum, built at compile
+//! time. See `cpsc323_lexer::derive` for the attributes and how ties
+//! between rules are broken.
+//!
+//! ```
+//! use cpsc323_lexer::derive::TokenKind;
+//! use cpsc323_lexer_derive::Lexer;
+//!
+//! #[derive(Lexer, Debug, Clone, Copy, PartialEq)]
+//! #[skip("[ \t\n]+")]
+//! enum Tok {
+//!     #[token("while")]
+//!     While,
+//!     #[regex("[A-Za-z_][A-Za-z0-9_]*")]
+//!     Identifier,
+//! }
+//!
+//! let toks: Vec<_> = Tok::lexer("while whilex").map(|t| t.unwrap().ty).collect();
+//! assert_eq!(toks, [Tok::While, Tok::Identifier]);
+//! ```
+
+use cpsc323_lexer::nfa::Nfa;
+use cpsc323_lexer::regex::Regex;
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+#[proc_macro_derive(Lexer, attributes(token, regex, skip))]
+pub fn derive_lexer(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(ts) => ts.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+// A pattern, where it came from, and what it yields (None to skip)
+struct Rule {
+    regex: Regex,
+    span: Span,
+    variant: Option<syn::Ident>,
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => return Err(syn::Error::new_spanned(name, "#[derive(Lexer)] only works on enums")),
+    };
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&input.generics, "token kind enums can't be generic"));
+    }
+
+    // Tokens first, then regexes, then skips; declaration order within each
+    let mut tokens = Vec::new();
+    let mut regexes = Vec::new();
+    let mut skips = Vec::new();
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("skip")) {
+        let lit: LitStr = attr.parse_args()?;
+        skips.push(Rule {
+            regex: parse(&lit, Regex::parse)?,
+            span: lit.span(),
+            variant: None,
+        });
+    }
+    for v in &data.variants {
+        if !matches!(v.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(v, "token kinds can't have fields"));
+        }
+        for attr in &v.attrs {
+            let (list, compile): (_, fn(&str) -> _) = match attr.path() {
+                p if p.is_ident("token") => (&mut tokens, Regex::literal),
+                p if p.is_ident("regex") => (&mut regexes, Regex::parse),
+                _ => continue,
+            };
+            let lit: LitStr = attr.parse_args()?;
+            list.push(Rule {
+                regex: parse(&lit, compile)?,
+                span: lit.span(),
+                variant: Some(v.ident.clone()),
+            });
+        }
+    }
+    let rules: Vec<Rule> = tokens.into_iter().chain(regexes).chain(skips).collect();
+    if rules.is_empty() {
+        return Err(syn::Error::new_spanned(name, "no #[token] or #[regex] rules"));
+    }
+
+    let res: Vec<Regex> = rules.iter().map(|r| r.regex.clone()).collect();
+    let dfa = Nfa::from_rules(&res).to_dfa().minimize();
+    if let Some(r) = dfa.accept(dfa.start()) {
+        return Err(syn::Error::new(rules[r].span, "pattern matches the empty string"));
+    }
+
+    let classes: Vec<u8> = (0..128u8).map(|c| dfa.class_of(c as char)).collect();
+    let non_ascii = dfa.class_of('\u{80}');
+    let num_classes = dfa.num_classes();
+    let num_states = dfa.num_states();
+    let start = dfa.start();
+    let trans = (0..num_states).flat_map(|s| (0..num_classes).map(move |c| (s, c as u8)));
+    let trans: Vec<usize> = trans.map(|(s, c)| dfa.next(s, c)).collect();
+    let accept = (0..num_states).map(|s| match dfa.accept(s) {
+        None => quote!(None),
+        Some(r) => match &rules[r].variant {
+            Some(v) => quote!(Some(Some(#name::#v))),
+            None => quote!(Some(None)),
+        },
+    });
+
+    Ok(quote! {
+        impl ::cpsc323_lexer::derive::TokenKind for #name {
+            fn dfa() -> &'static ::cpsc323_lexer::dfa::Dfa<Option<Self>> {
+                use ::cpsc323_lexer::dfa::Dfa;
+                static DFA: ::std::sync::OnceLock<Dfa<Option<#name>>> = ::std::sync::OnceLock::new();
+                DFA.get_or_init(|| {
+                    const CLASSES: [u8; 128] = [#(#classes),*];
+                    const TRANS: &[usize] = &[#(#trans),*];
+                    let accept: [Option<Option<#name>>; #num_states] = [#(#accept),*];
+                    let mut dfa = Dfa::new(CLASSES, #non_ascii, #num_classes, #num_states, #start);
+                    for (i, &to) in TRANS.iter().enumerate() {
+                        dfa.set_transition(i / #num_classes, (i % #num_classes) as u8, to);
+                    }
+                    for (s, a) in accept.into_iter().enumerate() {
+                        dfa.set_accept(s, a);
+                    }
+                    dfa
+                })
+            }
+        }
+    })
+}
+
+fn parse<E: std::fmt::Display>(lit: &LitStr, compile: impl Fn(&str) -> Result<Regex, E>) -> syn::Result<Regex> {
+    compile(&lit.value()).map_err(|e| syn::Error::new(lit.span(), e))
+}
@@ -0,0 +1,14 @@
+# The token regexes from "FSA Regex_Graphs.pdf", as a spec. `--verify`
+# checks the built-in lexer against these by default.
+#
+# The PDF lists `while` as both a keyword and an identifier; listing the
+# keyword first gives it the priority the lexer does.
+
+%%
+
+while                     emit Keyword
+[A-Za-z_][A-Za-z0-9_]*    emit Identifier
+[0-9]+                    emit Number
+[0-9]+\.[0-9]+            emit Real
+[();]                     emit Separator
+[<>=]                     emit Operator
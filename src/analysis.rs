@@ -0,0 +1,82 @@
+//! Finding conflicts between token rules.
+//!
+//! Rules are [`Regex`]es in priority order, as in a [`spec`](crate::spec):
+//! the longest match wins, and between rules matching the same text, the
+//! earlier one. Two rules *overlap* when some text matches both; that's
+//! often intended (keywords are also identifiers), and the earlier rule
+//! always gets it. A rule is *shadowed* when every text it matches is
+//! matched by earlier rules too, so it can never win, which is almost
+//! always a mistake.
+//!
+//! ```
+//! use cpsc323_lexer::{analysis, regex::Regex};
+//!
+//! let rules = ["[a-z]+", "while", "[0-9]+"].map(|p| Regex::parse(p).unwrap());
+//! let report = analysis::analyze(&rules);
+//! assert_eq!(report.overlaps[0].rules, (0, 1));
+//! assert_eq!(report.overlaps[0].witness, "while");
+//! assert_eq!(report.shadowed[0].rule, 1);
+//! ```
+
+use crate::dfa::{shortest_witness, Dfa};
+use crate::nfa::Nfa;
+use crate::regex::Regex;
+
+/// Two rules that match some of the same text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overlap {
+    /// The rules, higher priority first; that one wins the overlap
+    pub rules: (usize, usize),
+    /// The shortest text both match
+    pub witness: String,
+}
+
+/// A rule that can never win.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shadowed {
+    pub rule: usize,
+    /// The higher-priority rules it overlaps, which between them match
+    /// everything it does. Empty if the rule matches nothing at all.
+    pub by: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    /// Every overlapping pair, in rule order
+    pub overlaps: Vec<Overlap>,
+    pub shadowed: Vec<Shadowed>,
+}
+
+/// Check every pair of rules for overlap, and every rule for being
+/// shadowed by the ones before it.
+pub fn analyze(rules: &[Regex]) -> Report {
+    let dfas: Vec<Dfa<usize>> = rules
+        .iter()
+        .map(|re| Nfa::from_rules(std::slice::from_ref(re)).to_dfa().minimize())
+        .collect();
+    let mut report = Report::default();
+    for (j, later) in dfas.iter().enumerate() {
+        for (i, earlier) in dfas[..j].iter().enumerate() {
+            let both = |a: Option<usize>, b: Option<usize>| a.is_some() && b.is_some();
+            if let Some(witness) = shortest_witness(earlier, earlier.start(), later, later.start(), both) {
+                report.overlaps.push(Overlap {
+                    rules: (i, j),
+                    witness,
+                });
+            }
+        }
+        // Shadowed if nothing it matches escapes the union of earlier rules
+        let union = Nfa::from_rules(&rules[..j]).to_dfa().minimize();
+        let escapes = |a: Option<usize>, b: Option<usize>| a.is_some() && b.is_none();
+        if shortest_witness(later, later.start(), &union, union.start(), escapes).is_none() {
+            let by = report
+                .overlaps
+                .iter()
+                .filter(|o| o.rules.1 == j)
+                .map(|o| o.rules.0)
+                .collect();
+            report.shadowed.push(Shadowed { rule: j, by });
+        }
+    }
+    report
+}
@@ -0,0 +1,104 @@
+use memchr::{memchr, memchr2};
+
+use crate::cursor::Cursor;
+use crate::{LexerError, Span};
+
+/// A comment form the lexer should recognize.
+///
+/// The defaults are C's `//` and `/* */` plus the course's `[* *]`; see
+/// [`Lexer::comments`](crate::Lexer::comments) to change them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommentSyntax {
+    open: String,
+    // None for line comments, which end at the newline
+    close: Option<String>,
+}
+
+impl CommentSyntax {
+    /// A comment running from `open` to the end of the line.
+    ///
+    /// Panics if `open` is empty.
+    pub fn line(open: &str) -> Self {
+        assert!(!open.is_empty(), "comment delimiters can't be empty");
+        CommentSyntax {
+            open: open.to_string(),
+            close: None,
+        }
+    }
+
+    /// A comment running from `open` to the next `close`.
+    ///
+    /// Panics if either delimiter is empty.
+    pub fn block(open: &str, close: &str) -> Self {
+        assert!(
+            !open.is_empty() && !close.is_empty(),
+            "comment delimiters can't be empty"
+        );
+        CommentSyntax {
+            open: open.to_string(),
+            close: Some(close.to_string()),
+        }
+    }
+
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            CommentSyntax::line("//"),
+            CommentSyntax::block("/*", "*/"),
+            CommentSyntax::block("[*", "*]"),
+        ]
+    }
+
+    pub fn open(&self) -> &str {
+        &self.open
+    }
+
+    /// The closing delimiter, or `None` for a line comment.
+    pub fn close(&self) -> Option<&str> {
+        self.close.as_deref()
+    }
+}
+
+// The comment form starting at the cursor, if any
+pub(crate) fn starting_at<'a>(chas: &Cursor, syntaxes: &'a [CommentSyntax]) -> Option<&'a CommentSyntax> {
+    let rest = chas.rest();
+    syntaxes.iter().find(|s| rest.starts_with(&s.open))
+}
+
+// Consume a whole comment starting at the cursor and return its span
+pub(crate) fn skip(chas: &mut Cursor, syn: &CommentSyntax, nested: bool) -> Result<Span, LexerError> {
+    let start = chas.here();
+    chas.eat(&syn.open);
+    let opener = chas.to(start);
+    let close = match &syn.close {
+        Some(close) => close,
+        None => {
+            let rest = chas.rest().as_bytes();
+            chas.advance(memchr(b'\n', rest).unwrap_or(rest.len()));
+            return Ok(chas.to(start));
+        }
+    };
+    let mut depth = 1;
+    loop {
+        if chas.eat(close) {
+            depth -= 1;
+            if depth == 0 {
+                return Ok(chas.to(start));
+            }
+        } else if nested && chas.eat(&syn.open) {
+            depth += 1;
+        } else {
+            // Jump to the next place a delimiter could start. Their first
+            // bytes always start a character, so that's a safe place to stop
+            let rest = chas.rest().as_bytes();
+            if rest.is_empty() {
+                return Err(LexerError::UnterminatedComment(opener));
+            }
+            let (c, o) = (close.as_bytes()[0], syn.open.as_bytes()[0]);
+            let next = match nested {
+                true => memchr2(c, o, &rest[1..]),
+                false => memchr(c, &rest[1..]),
+            };
+            chas.advance(next.map_or(rest.len(), |i| i + 1));
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use memchr::{memchr_iter, memrchr};
+
+use crate::Span;
+
+// Walks the source so we always know where we are in it
+#[derive(Clone)]
+pub(crate) struct Cursor<'a> {
+    src: &'a str,
+    pub offset: usize,
+    line: usize,
+    col: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Cursor {
+            src,
+            offset: 0,
+            line: 1,
+            col: 1,
+        }
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        match self.peek_byte()? {
+            b if b.is_ascii() => Some(b as char),
+            _ => self.src[self.offset..].chars().next(),
+        }
+    }
+
+    // The next byte, which may be the start of a longer character
+    pub fn peek_byte(&self) -> Option<u8> {
+        self.src.as_bytes().get(self.offset).copied()
+    }
+
+    pub fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+        Some(c)
+    }
+
+    // Zero-width span at the current position; widened with `to`
+    pub fn here(&self) -> Span {
+        Span {
+            start: self.offset,
+            end: self.offset,
+            line: self.line,
+            col: self.col,
+        }
+    }
+
+    pub fn to(&self, start: Span) -> Span {
+        Span {
+            end: self.offset,
+            ..start
+        }
+    }
+
+    pub fn slice(&self, span: Span) -> &'a str {
+        &self.src[span.start..span.end]
+    }
+
+    // Everything not yet consumed
+    pub fn rest(&self) -> &'a str {
+        &self.src[self.offset..]
+    }
+
+    // Consume the next `len` bytes, which must end on a character boundary
+    pub fn advance(&mut self, len: usize) {
+        let skipped = &self.src.as_bytes()[self.offset..self.offset + len];
+        self.offset += len;
+        // Most tokens are a few bytes long, too few for memchr to pay off
+        if len < 32 {
+            for &b in skipped {
+                if b == b'\n' {
+                    self.line += 1;
+                    self.col = 1;
+                } else if (b as i8) >= -0x40 {
+                    self.col += 1;
+                }
+            }
+            return;
+        }
+        // Only what's after the last newline counts towards the column
+        let tail = match memrchr(b'\n', skipped) {
+            Some(i) => {
+                self.line += memchr_iter(b'\n', skipped).count();
+                self.col = 1;
+                &skipped[i + 1..]
+            }
+            None => skipped,
+        };
+        // One character per byte that isn't a UTF-8 continuation byte
+        self.col += tail.iter().filter(|&&b| (b as i8) >= -0x40).count();
+    }
+
+    // Consume `s` if the input continues with it
+    pub fn eat(&mut self, s: &str) -> bool {
+        if !self.rest().starts_with(s) {
+            return false;
+        }
+        self.advance(s.len());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Where `len` bytes into `src` is, as (line, col)
+    fn at(src: &str, len: usize) -> (usize, usize) {
+        let mut chas = Cursor::new(src);
+        chas.advance(len);
+        let here = chas.here();
+        assert_eq!(here.start, len);
+        (here.line, here.col)
+    }
+
+    #[test]
+    fn columns_count_characters() {
+        assert_eq!(at("abc", 3), (1, 4));
+        // Two bytes each, one column each
+        assert_eq!(at("ñandú = 1", 7), (1, 6));
+        assert_eq!(at("größe", "grö".len()), (1, 4));
+        assert_eq!(at("🦀x", 4), (1, 2));
+    }
+
+    #[test]
+    fn lines_restart_columns() {
+        assert_eq!(at("a\nbc", 2), (2, 1));
+        assert_eq!(at("a\nbc", 4), (2, 3));
+        assert_eq!(at("\n\n\n", 3), (4, 1));
+        // A carriage return is just another character on its line
+        assert_eq!(at("a\r\nb", 2), (1, 3));
+        assert_eq!(at("a\r\nb", 3), (2, 1));
+        assert_eq!(at("a\r\nb", 4), (2, 2));
+    }
+
+    #[test]
+    fn long_advances_agree_with_stepping() {
+        // Long enough for advance to take the memchr path
+        let src = "first line, long enough to matter\r\nsecond: ñandú größe 🦀\n\nfourth and last line here";
+        for len in (0..=src.len()).filter(|&i| src.is_char_boundary(i)) {
+            let mut stepped = Cursor::new(src);
+            while stepped.offset < len {
+                stepped.next();
+            }
+            let mut jumped = Cursor::new(src);
+            jumped.advance(len);
+            assert_eq!(jumped.here(), stepped.here(), "after {} bytes", len);
+        }
+    }
+
+    #[test]
+    fn spans_cover_what_was_consumed() {
+        let mut chas = Cursor::new("x\n  größe;");
+        chas.advance(4);
+        let start = chas.here();
+        assert!(chas.eat("größe"));
+        let span = chas.to(start);
+        assert_eq!(span, Span { start: 4, end: 11, line: 2, col: 3 });
+        assert_eq!(chas.slice(span), "größe");
+        assert_eq!(chas.here().col, 8);
+        assert!(!chas.eat("x"));
+    }
+}
@@ -0,0 +1,69 @@
+//! Runtime support for lexers generated by `#[derive(Lexer)]` from the
+//! `cpsc323-lexer-derive` crate.
+//!
+//! The derive goes on a fieldless enum of token kinds. Each variant lists
+//! the literal strings (`#[token("...")]`) and [`regex`](crate::regex)
+//! patterns (`#[regex("...")]`) it matches, and `#[skip("...")]` on the
+//! enum itself gives patterns to skip between tokens. The macro compiles
+//! them all into one DFA at build time and implements [`TokenKind`].
+//!
+//! The longest match wins. Between rules matching the same text, `token`s
+//! beat `regex`es, which beat `skip`s, and otherwise the variant declared
+//! first wins; so `#[token("while")]` takes `while` from an identifier
+//! regex, but not `whilex`.
+
+use crate::cursor::Cursor;
+use crate::dfa::Dfa;
+use crate::scan;
+use crate::{LexerError, Span};
+
+/// A token kind enum with a generated lexer.
+pub trait TokenKind: Copy + 'static {
+    /// The generated automaton. States accepting `Some(kind)` end a token
+    /// of that kind; those accepting `None` end text to skip.
+    fn dfa() -> &'static Dfa<Option<Self>>;
+
+    fn lexer(src: &str) -> Lexer<'_, Self> {
+        Lexer {
+            chas: Cursor::new(src),
+            dfa: Self::dfa(),
+        }
+    }
+}
+
+/// A token of kind `T`, borrowing its lexeme from the source.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Token<'src, T> {
+    pub ty: T,
+    pub lex: &'src str,
+    pub span: Span,
+}
+
+/// Iterator over the tokens of a source string, for a [`TokenKind`].
+///
+/// A character no rule matches is yielded as
+/// [`LexerError::IllegalCharacter`], and lexing carries on after it.
+pub struct Lexer<'src, T: 'static> {
+    chas: Cursor<'src>,
+    dfa: &'static Dfa<Option<T>>,
+}
+
+impl<'src, T: TokenKind> Lexer<'src, T> {
+    /// Lex the whole input; like [`crate::Lexer::tokenize`], this keeps
+    /// going after errors and hands them all back at the end.
+    pub fn tokenize(self) -> (Vec<Token<'src, T>>, Vec<LexerError>) {
+        scan::tokenize(self)
+    }
+}
+
+impl<'src, T: TokenKind> Iterator for Lexer<'src, T> {
+    type Item = Result<Token<'src, T>, LexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        scan::next(&mut self.chas, self.dfa, |kind, lex, span| {
+            kind.map(|ty| Ok(Token { ty, lex, span }))
+        })
+    }
+}
+
+impl<T: TokenKind> std::iter::FusedIterator for Lexer<'_, T> {}
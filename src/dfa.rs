@@ -0,0 +1,492 @@
+//! Deterministic finite automata over character classes.
+//!
+//! A [`Dfa`] is just tables: which class each character belongs to, a
+//! transition matrix indexed by state and class, and what (if anything) each
+//! state accepts. The lexer's own automaton lives in [`crate::table`]; to
+//! build one from regexes instead, see [`crate::regex`].
+
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::hash::Hash;
+
+/// Index of a state in a [`Dfa`].
+pub type StateId = usize;
+
+/// The dead state. Every DFA has one, it never accepts, and all of its
+/// transitions lead back to it.
+pub const DEAD: StateId = 0;
+
+// Byte class meaning "non-ASCII, decode the character to classify it"
+const DECODE: u8 = u8::MAX;
+
+/// One move made while matching: on character `c`, `offset` bytes into
+/// the input, of class `class`, from state `from` to state `to`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step {
+    pub offset: usize,
+    pub c: char,
+    pub from: StateId,
+    pub class: u8,
+    pub to: StateId,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dfa<A> {
+    // Class of each byte, so matching can step on bytes. Every non-ASCII
+    // byte is DECODE
+    bytes: [u8; 256],
+    // Class of every non-ASCII character
+    non_ascii: u8,
+    num_classes: usize,
+    // num_states * num_classes, row-major by state
+    trans: Vec<StateId>,
+    accept: Vec<Option<A>>,
+    start: StateId,
+}
+
+impl<A: Copy> Dfa<A> {
+    /// A DFA with `num_states` states (including [`DEAD`]) whose transitions
+    /// all go to the dead state, and that accepts nothing. There can be at
+    /// most 255 classes.
+    pub fn new(
+        classes: [u8; 128],
+        non_ascii: u8,
+        num_classes: usize,
+        num_states: usize,
+        start: StateId,
+    ) -> Self {
+        assert!(
+            classes.iter().chain([&non_ascii]).all(|&c| (c as usize) < num_classes),
+            "character class out of range"
+        );
+        assert!(num_classes <= DECODE as usize, "too many character classes");
+        assert!(start < num_states, "start state out of range");
+        let mut bytes = [DECODE; 256];
+        bytes[..128].copy_from_slice(&classes);
+        Dfa {
+            bytes,
+            non_ascii,
+            num_classes,
+            trans: vec![DEAD; num_states * num_classes],
+            accept: vec![None; num_states],
+            start,
+        }
+    }
+
+    pub fn set_transition(&mut self, from: StateId, class: u8, to: StateId) {
+        assert!(to < self.num_states(), "state out of range");
+        if from != DEAD {
+            self.trans[from * self.num_classes + class as usize] = to;
+        }
+    }
+
+    pub fn set_accept(&mut self, state: StateId, accept: Option<A>) {
+        self.accept[state] = accept;
+    }
+
+    pub fn num_states(&self) -> usize {
+        self.accept.len()
+    }
+
+    pub fn num_classes(&self) -> usize {
+        self.num_classes
+    }
+
+    pub fn start(&self) -> StateId {
+        self.start
+    }
+
+    pub fn class_of(&self, c: char) -> u8 {
+        if c.is_ascii() {
+            self.bytes[c as usize]
+        } else {
+            self.non_ascii
+        }
+    }
+
+    pub fn next(&self, state: StateId, class: u8) -> StateId {
+        self.trans[state * self.num_classes + class as usize]
+    }
+
+    pub fn accept(&self, state: StateId) -> Option<A> {
+        self.accept[state]
+    }
+
+    /// Maximal munch: run from `state` over `input` for as long as there
+    /// are live transitions, and return what the last accepting state seen
+    /// accepted along with the length in bytes of the text it matched.
+    ///
+    /// `class_of` classifies each character, so callers can refine the
+    /// table's classes (the lexer does for Unicode identifiers).
+    pub fn longest_match(
+        &self,
+        state: StateId,
+        input: &str,
+        class_of: impl FnMut(char) -> u8,
+    ) -> Option<(A, usize)> {
+        self.longest_match_traced(state, input, class_of, |_| {})
+    }
+
+    /// [`Dfa::longest_match`], stepping on bytes instead of characters,
+    /// which is faster, most of all through long runs of one state like
+    /// identifiers and whitespace. ASCII bytes are classified by table lookup,
+    /// and a character is only decoded when it isn't ASCII; `non_ascii`
+    /// classifies those.
+    pub fn longest_match_bytes(
+        &self,
+        mut state: StateId,
+        input: &str,
+        mut non_ascii: impl FnMut(char) -> u8,
+    ) -> Option<(A, usize)> {
+        let bytes = input.as_bytes();
+        let mut last = self.accept(state).map(|a| (a, 0));
+        let mut i = 0;
+        while let Some(&b) = bytes.get(i) {
+            let (class, len) = match self.bytes[b as usize] {
+                DECODE => {
+                    let c = input[i..].chars().next().unwrap_or_default();
+                    (non_ascii(c), c.len_utf8())
+                }
+                class => (class, 1),
+            };
+            let next = self.next(state, class);
+            if next == DEAD {
+                break;
+            }
+            i += len;
+            if next == state {
+                // Stay as long as the input lets us, as through the rest of
+                // an identifier or a run of whitespace, with nothing but
+                // table lookups per byte
+                while let Some(&b) = bytes.get(i) {
+                    let class = self.bytes[b as usize];
+                    if class == DECODE || self.next(state, class) != state {
+                        break;
+                    }
+                    i += 1;
+                }
+            }
+            state = next;
+            if let Some(a) = self.accept(state) {
+                last = Some((a, i));
+            }
+        }
+        last
+    }
+
+    /// [`Dfa::longest_match`], calling `on_step` with every move made,
+    /// including the final one into [`DEAD`] that ends the match.
+    pub fn longest_match_traced(
+        &self,
+        mut state: StateId,
+        input: &str,
+        mut class_of: impl FnMut(char) -> u8,
+        mut on_step: impl FnMut(Step),
+    ) -> Option<(A, usize)> {
+        let mut last = self.accept(state).map(|a| (a, 0));
+        for (i, c) in input.char_indices() {
+            let class = class_of(c);
+            let next = self.next(state, class);
+            on_step(Step {
+                offset: i,
+                c,
+                from: state,
+                class,
+                to: next,
+            });
+            state = next;
+            if state == DEAD {
+                break;
+            }
+            if let Some(a) = self.accept(state) {
+                last = Some((a, i + c.len_utf8()));
+            }
+        }
+        last
+    }
+
+    /// Relabel what the accepting states accept, e.g. to turn the rule
+    /// numbers from [`crate::regex::compile`] into token kinds.
+    pub fn map<B: Copy>(&self, mut f: impl FnMut(A) -> B) -> Dfa<B> {
+        Dfa {
+            bytes: self.bytes,
+            non_ascii: self.non_ascii,
+            num_classes: self.num_classes,
+            trans: self.trans.clone(),
+            accept: self.accept.iter().map(|a| a.map(&mut f)).collect(),
+            start: self.start,
+        }
+    }
+}
+
+impl<A: Copy + Eq + Hash> Dfa<A> {
+    /// The equivalent DFA with the fewest states, by Hopcroft's partition
+    /// refinement. States only reachable from nowhere are kept; states that
+    /// can never reach an accepting one merge into [`DEAD`].
+    pub fn minimize(&self) -> Dfa<A> {
+        let n = self.num_states();
+        // Start from one block per distinct accept value
+        let mut block_of = vec![0; n];
+        let mut blocks: Vec<Vec<StateId>> = Vec::new();
+        let mut by_accept: HashMap<Option<A>, usize> = HashMap::new();
+        for (s, block) in block_of.iter_mut().enumerate() {
+            *block = *by_accept.entry(self.accept(s)).or_insert_with(|| {
+                blocks.push(Vec::new());
+                blocks.len() - 1
+            });
+            blocks[*block].push(s);
+        }
+
+        // Predecessors of each state, per class
+        let mut preds = vec![Vec::new(); n * self.num_classes];
+        for s in 0..n {
+            for c in 0..self.num_classes {
+                preds[self.next(s, c as u8) * self.num_classes + c].push(s);
+            }
+        }
+
+        let mut todo: Vec<usize> = (0..blocks.len()).collect();
+        let mut in_todo = vec![true; blocks.len()];
+        while let Some(a) = todo.pop() {
+            in_todo[a] = false;
+            // Splits below may shrink block `a`; split by all of it anyway
+            let splitter = blocks[a].clone();
+            for c in 0..self.num_classes {
+                // The states with a move on `c` into block `a`, by block
+                let mut hits: BTreeMap<usize, Vec<StateId>> = BTreeMap::new();
+                for &s in &splitter {
+                    for &p in &preds[s * self.num_classes + c] {
+                        hits.entry(block_of[p]).or_default().push(p);
+                    }
+                }
+                for (y, mut x) in hits {
+                    x.sort_unstable();
+                    x.dedup();
+                    if x.len() == blocks[y].len() {
+                        continue;
+                    }
+                    // Split `y`: the states in `x` move to a new block
+                    let z = blocks.len();
+                    blocks[y].retain(|s| x.binary_search(s).is_err());
+                    for &s in &x {
+                        block_of[s] = z;
+                    }
+                    blocks.push(x);
+                    if in_todo[y] || blocks[z].len() < blocks[y].len() {
+                        todo.push(z);
+                        in_todo.push(true);
+                    } else {
+                        todo.push(y);
+                        in_todo[y] = true;
+                        in_todo.push(false);
+                    }
+                }
+            }
+        }
+
+        // Number the new states in order of their lowest old state, so the
+        // dead state stays at 0 and the numbering doesn't depend on the
+        // order blocks were split in
+        blocks.sort_unstable_by_key(|b| b.iter().min().copied());
+        for (b, states) in blocks.iter().enumerate() {
+            for &s in states {
+                block_of[s] = b;
+            }
+        }
+        let mut classes = [0; 128];
+        classes.copy_from_slice(&self.bytes[..128]);
+        let mut min = Dfa::new(
+            classes,
+            self.non_ascii,
+            self.num_classes,
+            blocks.len(),
+            block_of[self.start],
+        );
+        for (b, states) in blocks.iter().enumerate() {
+            let s = states[0];
+            for c in 0..self.num_classes {
+                min.set_transition(b, c as u8, block_of[self.next(s, c as u8)]);
+            }
+            min.set_accept(b, self.accept(s));
+        }
+        min
+    }
+}
+
+/// The shortest string that runs `a` from `a_start` and `b` from
+/// `b_start` to a pair of states where `goal` holds of what they accept,
+/// found by breadth-first search of the product automaton. Ties go to the
+/// string that comes first in ASCII order. Every non-ASCII character is
+/// the same to a [`Dfa`], so `é` stands in for all of them.
+pub fn shortest_witness<A: Copy, B: Copy>(
+    a: &Dfa<A>,
+    a_start: StateId,
+    b: &Dfa<B>,
+    b_start: StateId,
+    goal: impl Fn(Option<A>, Option<B>) -> bool,
+) -> Option<String> {
+    let alphabet: Vec<char> = (0..128u8).map(char::from).chain(['é']).collect();
+    type Pair = (StateId, StateId);
+    let start = (a_start, b_start);
+    // How each pair was first reached: the pair before it and the character
+    let mut came_from: HashMap<Pair, Option<(Pair, char)>> = HashMap::new();
+    came_from.insert(start, None);
+    let mut queue = VecDeque::from([start]);
+    while let Some(pair @ (s, t)) = queue.pop_front() {
+        if goal(a.accept(s), b.accept(t)) {
+            let mut witness = Vec::new();
+            let mut at = pair;
+            while let Some(&Some((prev, c))) = came_from.get(&at) {
+                witness.push(c);
+                at = prev;
+            }
+            return Some(witness.into_iter().rev().collect());
+        }
+        // Nothing leaves the dead state, so there's nothing more to find
+        if (s, t) == (DEAD, DEAD) {
+            continue;
+        }
+        for &c in &alphabet {
+            let next = (a.next(s, a.class_of(c)), b.next(t, b.class_of(c)));
+            if let Entry::Vacant(e) = came_from.entry(next) {
+                e.insert(Some((pair, c)));
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// The product of `a` from `a_start` and `b` from `b_start`: a DFA that
+/// runs both at once, accepting `f` of what each accepts. Its classes
+/// refine both inputs' classes. Only reachable pairs become states, and
+/// the pair of dead states is the product's dead state.
+pub fn product<A: Copy, B: Copy, C: Copy>(
+    a: &Dfa<A>,
+    a_start: StateId,
+    b: &Dfa<B>,
+    b_start: StateId,
+    f: impl Fn(Option<A>, Option<B>) -> Option<C>,
+) -> Dfa<C> {
+    // A class for each pair of classes some character has
+    let mut class_ids: HashMap<(u8, u8), u8> = HashMap::new();
+    let mut reps: Vec<char> = Vec::new();
+    let mut class_of = |c: char| {
+        *class_ids.entry((a.class_of(c), b.class_of(c))).or_insert_with(|| {
+            reps.push(c);
+            reps.len() as u8 - 1
+        })
+    };
+    let mut classes = [0; 128];
+    for (c, class) in classes.iter_mut().enumerate() {
+        *class = class_of(c as u8 as char);
+    }
+    let non_ascii = class_of('é');
+
+    let mut ids: HashMap<(StateId, StateId), StateId> = HashMap::from([((DEAD, DEAD), DEAD)]);
+    let mut pairs = vec![(DEAD, DEAD), (a_start, b_start)];
+    let start = *ids.entry((a_start, b_start)).or_insert(1);
+    if start == DEAD {
+        pairs.pop();
+    }
+    let mut trans = Vec::new();
+    let mut todo = VecDeque::from([start]);
+    while let Some(from) = todo.pop_front() {
+        let (s, t) = pairs[from];
+        for (class, &c) in reps.iter().enumerate() {
+            let pair = (a.next(s, a.class_of(c)), b.next(t, b.class_of(c)));
+            let to = *ids.entry(pair).or_insert_with(|| {
+                pairs.push(pair);
+                todo.push_back(pairs.len() - 1);
+                pairs.len() - 1
+            });
+            trans.push((from, class as u8, to));
+        }
+    }
+
+    let mut dfa = Dfa::new(classes, non_ascii, reps.len(), pairs.len(), start);
+    for (from, class, to) in trans {
+        dfa.set_transition(from, class, to);
+    }
+    for (id, &(s, t)) in pairs.iter().enumerate().skip(1) {
+        dfa.set_accept(id, f(a.accept(s), b.accept(t)));
+    }
+    dfa
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nfa::Nfa;
+    use crate::regex::{self, Regex};
+
+    fn dfa(pattern: &str) -> Dfa<usize> {
+        Nfa::from_rules(&[Regex::parse(pattern).unwrap()]).to_dfa()
+    }
+
+    fn matches(dfa: &Dfa<usize>, s: &str) -> bool {
+        dfa.longest_match(dfa.start(), s, |c| dfa.class_of(c)).map(|m| m.1) == Some(s.len())
+    }
+
+    #[test]
+    fn minimize_textbook() {
+        // The dragon book's example: subset construction gives five states,
+        // the minimal DFA four, and both have the dead state on top
+        let subset = dfa("(a|b)*abb");
+        let min = subset.minimize();
+        assert_eq!(subset.num_states(), 6);
+        assert_eq!(min.num_states(), 5);
+        for s in ["abb", "aabb", "babb", "ababb"] {
+            assert!(matches(&min, s), "{}", s);
+        }
+        for s in ["", "ab", "abba", "abbb", "abc"] {
+            assert!(!matches(&min, s), "{}", s);
+        }
+        assert_eq!(min.accept(DEAD), None);
+    }
+
+    #[test]
+    fn minimize_keeps_rules_apart() {
+        // The states after `b` and after `c` accept the same rule, so merge
+        assert_eq!(dfa("a(b|c)").minimize().num_states(), 4);
+        // but not when they accept different ones
+        let rules = regex::compile(&["ab", "ac"]).unwrap();
+        assert_eq!(rules.num_states(), 5);
+    }
+
+    #[test]
+    fn matching_on_bytes_agrees() {
+        let dfa = regex::compile(&["[a-z_][a-z0-9_]*", "[0-9]+", "[ \t\n]+", "'[^']*'", "."]).unwrap();
+        let class_of = |c| dfa.class_of(c);
+        for s in ["abc def", "x1 ", "123abc", "'é s' x", "é", "  \n\tq", "", "__"] {
+            assert_eq!(
+                dfa.longest_match_bytes(dfa.start(), s, class_of),
+                dfa.longest_match(dfa.start(), s, class_of),
+                "{:?}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn witnesses() {
+        let (a, b) = (dfa("[a-z]+").minimize(), dfa("whi[a-z]*").minimize());
+        let both = |x: Option<usize>, y: Option<usize>| x.is_some() && y.is_some();
+        assert_eq!(shortest_witness(&a, a.start(), &b, b.start(), both).as_deref(), Some("whi"));
+        let only_b = |x: Option<usize>, y: Option<usize>| x.is_none() && y.is_some();
+        assert_eq!(shortest_witness(&a, a.start(), &b, b.start(), only_b), None);
+        // Ties go to the first string in ASCII order
+        let only_a = |x: Option<usize>, y: Option<usize>| x.is_some() && y.is_none();
+        assert_eq!(shortest_witness(&a, a.start(), &b, b.start(), only_a).as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn product_runs_both() {
+        let (a, b) = (dfa("[a-z]+").minimize(), dfa("[a-f0-9]+").minimize());
+        let both = product(&a, a.start(), &b, b.start(), |x, y| x.zip(y));
+        let m = |s| both.longest_match(both.start(), s, |c| both.class_of(c));
+        assert_eq!(m("face1"), Some(((0, 0), 4)));
+        assert_eq!(m("fog"), Some(((0, 0), 1)));
+        assert_eq!(m("9"), None);
+    }
+}
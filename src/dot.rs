@@ -0,0 +1,190 @@
+//! Graphviz DOT rendering of a [`Dfa`], for drawing the automaton from the
+//! tables themselves rather than by hand.
+//!
+//! ```
+//! use cpsc323_lexer::{dot::Dot, regex};
+//!
+//! let dfa = regex::compile(&["[0-9]+"]).unwrap();
+//! let dot = Dot::new(&dfa).to_string();
+//! assert!(dot.contains("label=\"[0-9]\""));
+//! ```
+
+use core::fmt;
+use std::collections::BTreeMap;
+
+use crate::dfa::{Dfa, StateId, DEAD};
+
+type Label<'a, T> = Box<dyn Fn(T) -> String + 'a>;
+
+/// A DFA ready to be printed as a DOT digraph with `Display`.
+///
+/// Edges are labelled with the characters they move on, with ranges
+/// collapsed (`[0-9A-F]`); accepting states are drawn as double circles
+/// and labelled with what they accept. The dead state and the edges into
+/// it are left out.
+pub struct Dot<'a, A> {
+    dfa: &'a Dfa<A>,
+    starts: Vec<StateId>,
+    state_name: Label<'a, StateId>,
+    accept_label: Label<'a, A>,
+    class_name: Box<dyn Fn(u8) -> Option<String> + 'a>,
+}
+
+impl<'a, A: Copy + fmt::Debug> Dot<'a, A> {
+    pub fn new(dfa: &'a Dfa<A>) -> Self {
+        Dot {
+            dfa,
+            starts: vec![dfa.start()],
+            state_name: Box::new(|s| format!("q{}", s)),
+            accept_label: Box::new(|a| format!("{:?}", a)),
+            class_name: Box::new(|_| None),
+        }
+    }
+
+    /// States to draw an entry arrow into. Defaults to the DFA's start.
+    pub fn starts(mut self, starts: &[StateId]) -> Self {
+        self.starts = starts.to_vec();
+        self
+    }
+
+    pub fn state_names(mut self, f: impl Fn(StateId) -> String + 'a) -> Self {
+        self.state_name = Box::new(f);
+        self
+    }
+
+    pub fn accept_labels(mut self, f: impl Fn(A) -> String + 'a) -> Self {
+        self.accept_label = Box::new(f);
+        self
+    }
+
+    /// Name character classes on edges instead of listing their
+    /// characters, where `f` returns `Some`.
+    pub fn class_names(mut self, f: impl Fn(u8) -> Option<String> + 'a) -> Self {
+        self.class_name = Box::new(f);
+        self
+    }
+
+    // The label for an edge moving on `classes`
+    fn edge_label(&self, classes: &[u8]) -> String {
+        let mut chars = [false; 128];
+        let mut names = Vec::new();
+        for &class in classes {
+            if let Some(name) = (self.class_name)(class) {
+                names.push(name);
+                continue;
+            }
+            for (c, member) in chars.iter_mut().enumerate() {
+                *member |= self.dfa.class_of(c as u8 as char) == class;
+            }
+            if self.dfa.class_of('\u{80}') == class {
+                names.push(String::from("non-ASCII"));
+            }
+        }
+        let set = char_set(&chars);
+        if !set.is_empty() {
+            names.insert(0, set);
+        }
+        names.join(", ")
+    }
+}
+
+impl<A: Copy + fmt::Debug> fmt::Display for Dot<'_, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dfa = self.dfa;
+        writeln!(f, "digraph dfa {{")?;
+        writeln!(f, "    rankdir=LR;")?;
+        writeln!(f, "    node [shape=circle];")?;
+        for (i, &s) in self.starts.iter().enumerate() {
+            writeln!(f, "    start{} [shape=point];", i)?;
+            writeln!(f, "    start{} -> q{};", i, s)?;
+        }
+        for s in (0..dfa.num_states()).filter(|&s| s != DEAD) {
+            let name = quote(&(self.state_name)(s));
+            match dfa.accept(s) {
+                Some(a) => writeln!(
+                    f,
+                    "    q{} [shape=doublecircle, label=\"{}\\n{}\"];",
+                    s,
+                    name,
+                    quote(&(self.accept_label)(a))
+                )?,
+                None => writeln!(f, "    q{} [label=\"{}\"];", s, name)?,
+            }
+        }
+        for from in (0..dfa.num_states()).filter(|&s| s != DEAD) {
+            // One edge per target, on all the classes that lead there
+            let mut edges: BTreeMap<StateId, Vec<u8>> = BTreeMap::new();
+            for class in 0..dfa.num_classes() as u8 {
+                let to = dfa.next(from, class);
+                if to != DEAD {
+                    edges.entry(to).or_default().push(class);
+                }
+            }
+            for (to, classes) in edges {
+                writeln!(
+                    f,
+                    "    q{} -> q{} [label=\"{}\"];",
+                    from,
+                    to,
+                    quote(&self.edge_label(&classes))
+                )?;
+            }
+        }
+        writeln!(f, "}}")
+    }
+}
+
+// A bracket expression for the characters marked in `chars`, or the
+// character alone if there's just one
+fn char_set(chars: &[bool; 128]) -> String {
+    let mut ranges = Vec::new();
+    let mut c = 0;
+    while c < 128 {
+        if !chars[c] {
+            c += 1;
+            continue;
+        }
+        let lo = c;
+        while c < 128 && chars[c] {
+            c += 1;
+        }
+        ranges.push((lo as u8, c as u8 - 1));
+    }
+    match ranges[..] {
+        [] => String::new(),
+        [(lo, hi)] if lo == hi => show(lo, false),
+        _ => {
+            let mut s = String::from("[");
+            for (lo, hi) in ranges {
+                s.push_str(&show(lo, true));
+                if hi > lo + 1 {
+                    s.push('-');
+                }
+                if hi > lo {
+                    s.push_str(&show(hi, true));
+                }
+            }
+            s.push(']');
+            s
+        }
+    }
+}
+
+fn show(c: u8, in_brackets: bool) -> String {
+    match c {
+        b'\n' => String::from("\\n"),
+        b'\t' => String::from("\\t"),
+        b'\r' => String::from("\\r"),
+        b'\\' => String::from("\\\\"),
+        b']' | b'^' | b'-' if in_brackets => format!("\\{}", c as char),
+        b' ' if in_brackets => String::from(" "),
+        b' ' => String::from("' '"),
+        c if c.is_ascii_graphic() => (c as char).to_string(),
+        c => format!("\\x{:02x}", c),
+    }
+}
+
+// Escape text for a double-quoted DOT string
+fn quote(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,142 @@
+//! Checking the built-in lexer against a spec.
+//!
+//! The lexer's automaton is hand-written in [`crate::table`], and the token
+//! regexes it's meant to implement live elsewhere (see `fsa.spec`). This
+//! compares the two per token type: for each kind a [`Spec`] emits, the
+//! strings the spec lexes as one token of that kind against the strings
+//! the lexer does, by product construction, with the shortest string they
+//! disagree on as the counterexample.
+//!
+//! Only what the automaton decides is compared. Strings and comments are
+//! scanned outside it, errors aren't tokens, and a match that gives back
+//! trailing context (the `1` of `1..`) isn't the whole string's token.
+//! The lexer is taken to be in its default start condition.
+
+use crate::dfa::{product, shortest_witness, Dfa};
+use crate::nfa::Nfa;
+use crate::regex::Regex;
+use crate::spec::{Action, Spec};
+use crate::table::{self, Accept, States};
+use crate::TokenType;
+
+/// A string the spec and the lexer lex differently.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    /// The kind of token they disagree about
+    pub kind: String,
+    /// The shortest such string
+    pub witness: String,
+    /// Whether it's the spec (rather than the lexer) that lexes `witness`
+    /// as `kind`
+    pub in_spec: bool,
+}
+
+/// The lexer's automaton with each accepting state labelled by the name of
+/// the token type it produces, splitting `keywords` off from identifiers.
+pub fn implementation<S: AsRef<str>>(keywords: &[S]) -> Dfa<&'static str> {
+    // Keywords that aren't ASCII can't be spelled in a regex, and aren't
+    // identifiers without Unicode mode anyway
+    let kws: Vec<Regex> = keywords
+        .iter()
+        .filter_map(|k| Regex::literal(k.as_ref()).ok())
+        .collect();
+    let kws = Nfa::from_rules(&kws).to_dfa();
+    let lexer = table::builtin();
+    product(lexer, States::Start as usize, &kws, kws.start(), |acc, kw| match acc? {
+        Accept::Identifier if kw.is_some() => Some(TokenType::Keyword.name()),
+        acc if acc.trailing() > 0 => None,
+        acc => acc.token_type().map(|ty| ty.name()),
+    })
+}
+
+/// Compare the lexer, with `keywords` reserved, against `spec`, for every
+/// kind of token the spec emits. Kinds are matched up by
+/// [`TokenType::name`].
+pub fn verify<S: AsRef<str>>(spec: &Spec, keywords: &[S]) -> Vec<Difference> {
+    let imp = implementation(keywords);
+    let dfa = spec.dfa();
+    let rules = spec.rules();
+    let kind_of = |rule: usize| match &rules[rule].action {
+        Action::Emit(kind) => Some(kind.as_str()),
+        _ => None,
+    };
+    let mut kinds: Vec<&str> = Vec::new();
+    for kind in (0..rules.len()).filter_map(kind_of) {
+        if !kinds.contains(&kind) {
+            kinds.push(kind);
+        }
+    }
+    let mut diffs = Vec::new();
+    for kind in kinds {
+        let differ = |a: Option<&str>, b: Option<usize>| {
+            (a == Some(kind)) != (b.and_then(kind_of) == Some(kind))
+        };
+        if let Some(witness) = shortest_witness(&imp, imp.start(), dfa, dfa.start(), differ) {
+            diffs.push(Difference {
+                kind: kind.to_string(),
+                in_spec: whole(&imp, &witness) != Some(kind),
+                witness,
+            });
+        }
+    }
+    diffs
+}
+
+// What `dfa` accepts after reading all of `s`
+fn whole<A: Copy>(dfa: &Dfa<A>, s: &str) -> Option<A> {
+    dfa.longest_match(dfa.start(), s, |c| dfa.class_of(c))
+        .filter(|&(_, len)| len == s.len())
+        .map(|(a, _)| a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DEFAULT_KEYWORDS;
+
+    #[test]
+    fn keywords_split_off_from_identifiers() {
+        let imp = implementation(&["if", "while"]);
+        assert_eq!(whole(&imp, "if"), Some("Keyword"));
+        assert_eq!(whole(&imp, "iff"), Some("Identifier"));
+        assert_eq!(whole(&imp, "whil"), Some("Identifier"));
+        assert_eq!(whole(&imp, "0x1F"), Some("Number"));
+        // Trailing context and errors aren't tokens of any kind
+        assert_eq!(whole(&imp, "1.."), None);
+        assert_eq!(whole(&imp, "12ab"), None);
+        assert_eq!(whole(&imp, "@"), None);
+    }
+
+    #[test]
+    fn fsa_spec_counterexamples() {
+        let spec = Spec::parse(include_str!("../fsa.spec")).unwrap();
+        let diff = |kind: &str, witness: &str, in_spec: bool| Difference {
+            kind: kind.to_string(),
+            witness: witness.to_string(),
+            in_spec,
+        };
+        assert_eq!(
+            verify(&spec, DEFAULT_KEYWORDS),
+            [
+                // The spec has no keywords, so they're identifiers to it
+                diff("Keyword", "if", false),
+                diff("Identifier", "if", true),
+                // Digit separators, reals like `0.`, and the separators and
+                // operators added since the PDF are all the lexer's own
+                diff("Number", "0_", false),
+                diff("Real", "0.", false),
+                diff("Separator", "#", false),
+                diff("Operator", "*", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn agreeing_specs_have_no_differences() {
+        let spec = Spec::parse("%%\n[A-Za-z_][A-Za-z0-9_]* emit Identifier\n\"<=\"|\">=\" emit Operator").unwrap();
+        let diffs = verify(&spec, &[] as &[&str]);
+        assert_eq!(diffs.len(), 1);
+        // Identifiers agree; the lexer just has more operators
+        assert_eq!((&*diffs[0].kind, diffs[0].in_spec), ("Operator", false));
+    }
+}
@@ -0,0 +1,80 @@
+use core::fmt;
+use std::fmt::Debug;
+
+use crate::Span;
+
+// If a LexerError is returned, the rest of the string should be considered void
+// (unless the lexer is recovering; see `Lexer::recovering`)
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum LexerError {
+    IllegalDot(Span),
+    InternalStateError,
+    InvalidIdentifier(Span),
+    /// Points at the opening quote.
+    UnterminatedString(Span),
+    /// Covers the backslash and whatever followed it.
+    InvalidEscape(Span),
+    /// Points at the comment's opening delimiter.
+    UnterminatedComment(Span),
+    /// An integer literal too big for a `u64`.
+    IntegerOverflow(Span),
+    /// A real literal that doesn't fit in an `f64`.
+    InvalidReal(Span),
+    /// A `0x`, `0o` or `0b` prefix with no digits after it.
+    MissingDigits(Span),
+    /// Points at a digit that doesn't belong to the literal's radix.
+    InvalidDigit(Span),
+    /// An `e` with no exponent digits after it, as in `1e` or `1e+`.
+    MissingExponent(Span),
+    /// A character that can't start or continue any token.
+    IllegalCharacter(char, Span),
+    /// Text matched by a [`spec`](crate::spec) rule whose action is
+    /// `error`, with the rule's message.
+    Rejected(String, Span),
+}
+
+impl LexerError {
+    /// Where the error happened, if the lexer knows.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            LexerError::IllegalDot(sp)
+            | LexerError::InvalidIdentifier(sp)
+            | LexerError::UnterminatedString(sp)
+            | LexerError::InvalidEscape(sp)
+            | LexerError::UnterminatedComment(sp)
+            | LexerError::IntegerOverflow(sp)
+            | LexerError::InvalidReal(sp)
+            | LexerError::MissingDigits(sp)
+            | LexerError::InvalidDigit(sp)
+            | LexerError::MissingExponent(sp)
+            | LexerError::IllegalCharacter(_, sp)
+            | LexerError::Rejected(_, sp) => Some(*sp),
+            LexerError::InternalStateError => None,
+        }
+    }
+}
+
+impl fmt::Display for LexerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexerError::IllegalDot(sp) => write!(f, "IllegalDot at {}", sp),
+            LexerError::InvalidIdentifier(sp) => write!(f, "InvalidIdentifier at {}", sp),
+            LexerError::UnterminatedString(sp) => write!(f, "UnterminatedString at {}", sp),
+            LexerError::InvalidEscape(sp) => write!(f, "InvalidEscape at {}", sp),
+            LexerError::UnterminatedComment(sp) => write!(f, "UnterminatedComment at {}", sp),
+            LexerError::IntegerOverflow(sp) => write!(f, "IntegerOverflow at {}", sp),
+            LexerError::InvalidReal(sp) => write!(f, "InvalidReal at {}", sp),
+            LexerError::MissingDigits(sp) => write!(f, "MissingDigits at {}", sp),
+            LexerError::InvalidDigit(sp) => write!(f, "InvalidDigit at {}", sp),
+            LexerError::MissingExponent(sp) => write!(f, "MissingExponent at {}", sp),
+            LexerError::IllegalCharacter(c, sp) => {
+                write!(f, "IllegalCharacter {:?} at {}", c, sp)
+            }
+            LexerError::Rejected(msg, sp) => write!(f, "{} at {}", msg, sp),
+            LexerError::InternalStateError => Debug::fmt(&self, f),
+        }
+    }
+}
+
+impl std::error::Error for LexerError {}
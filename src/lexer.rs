@@ -0,0 +1,812 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use memchr::memchr3;
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+use unicode_xid::UnicodeXID;
+
+use crate::comment::{self, CommentSyntax};
+use crate::cursor::Cursor;
+use crate::dfa::{Dfa, StateId, Step};
+use crate::runs;
+use crate::table::{self, Accept, Class, States};
+use crate::{LexerError, Literal, Radix, Separator, Span, Token, TokenType, TraceEvent};
+
+// Character class of `c` in the automaton. Unicode mode puts XID_Start
+// characters with the ASCII letters, following UAX #31's default identifier
+// syntax (with `_` also allowed to start one)
+fn classify(dfa: &Dfa<Accept>, c: char, unicode: bool) -> u8 {
+    if unicode && !c.is_ascii() {
+        if c.is_xid_start() {
+            return Class::Letter as u8;
+        }
+        if c.is_xid_continue() {
+            return Class::UnicodeContinue as u8;
+        }
+    }
+    dfa.class_of(c)
+}
+
+// Whitespace and identifiers make up most of a program, and a run of
+// either can be measured without stepping the automaton a byte at a time.
+// Same answer as the automaton, or `None` to leave it to it
+fn run(dfa: &Dfa<Accept>, state: States, input: &str) -> Option<(Accept, usize)> {
+    let bytes = input.as_bytes();
+    let b = *bytes.first().filter(|b| b.is_ascii())?;
+    let to = dfa.next(state as StateId, dfa.class_of(b as char));
+    let (acc, len) = if to == States::Blank as StateId {
+        (Accept::Whitespace, runs::blanks(bytes))
+    } else if to == States::DefiningIdentifier as StateId {
+        (Accept::Identifier, 1 + runs::ident_chars(&bytes[1..]))
+    } else {
+        return None;
+    };
+    // A non-ASCII character might carry the run on, depending on the mode
+    match bytes.get(len) {
+        Some(b) if !b.is_ascii() => None,
+        _ => Some((acc, len)),
+    }
+}
+
+// The automaton's longest match, reporting each move to `f`. Slower than
+// matching on bytes, so only used when tracing
+fn traced(
+    dfa: &Dfa<Accept>,
+    state: States,
+    start: Span,
+    input: &str,
+    unicode: bool,
+    f: &mut dyn FnMut(TraceEvent),
+) -> Option<(Accept, usize)> {
+    let (mut line, mut col) = (start.line, start.col);
+    let on_step = |step: Step| {
+        let at = start.start + step.offset;
+        f(TraceEvent::Step {
+            span: Span {
+                start: at,
+                end: at + step.c.len_utf8(),
+                line,
+                col,
+            },
+            c: step.c,
+            state: States::ALL[step.from],
+            class: Class::ALL[step.class as usize],
+            next: States::ALL[step.to],
+        });
+        if step.c == '\n' {
+            line += 1;
+            col = 1;
+        } else {
+            col += 1;
+        }
+    };
+    dfa.longest_match_traced(state as StateId, input, |c| classify(dfa, c, unicode), on_step)
+}
+
+// Characters that can never continue a broken token, so recovery restarts
+// lexing there
+fn is_sync(c: char) -> bool {
+    matches!(
+        c,
+        ' ' | '\t' | '\r' | '\n'
+            | '(' | ')' | '{' | '}' | '[' | ']' | ',' | ':' | ';' | '#' | '$'
+            | '+' | '-' | '*' | '/' | '<' | '>' | '=' | '!' | '.' | '"'
+    )
+}
+
+/// Iterator over the tokens of a source string.
+///
+/// Owns its input cursor and hands out one token per `next`. By default,
+/// once an error has been yielded the rest of the input is void, so the
+/// iterator is fused from then on. See [`Lexer::recovering`] for a mode that
+/// keeps going.
+pub struct Lexer<'src> {
+    chas: Cursor<'src>,
+    done: bool,
+    recover: bool,
+    errors: Vec<LexerError>,
+    start: Span,
+    comments: Vec<CommentSyntax>,
+    // Which bytes some comment form starts with
+    comment_starts: [bool; 256],
+    nested_comments: bool,
+    keep_comments: bool,
+    keywords: HashSet<String>,
+    // Which bytes some keyword starts with, so most identifiers are never
+    // hashed
+    keyword_starts: [bool; 256],
+    unicode: bool,
+    leading_dot_reals: bool,
+    // Last token handed out, ignoring comments; decides what a `.` means
+    prev: Option<TokenType>,
+    trace: Option<Box<dyn FnMut(TraceEvent) + 'src>>,
+}
+
+/// The reserved words of the course language, used unless
+/// [`Lexer::keywords`] says otherwise.
+pub const DEFAULT_KEYWORDS: &[&str] = &[
+    "boolean", "else", "endif", "false", "for", "function", "get", "if", "integer", "put", "real",
+    "return", "true", "while",
+];
+
+impl<'src> Lexer<'src> {
+    pub fn new(src: &'src str) -> Self {
+        let chas = Cursor::new(src);
+        Lexer {
+            start: chas.here(),
+            chas,
+            done: false,
+            recover: false,
+            errors: Vec::new(),
+            comments: Vec::new(),
+            comment_starts: [false; 256],
+            nested_comments: false,
+            keep_comments: false,
+            keywords: HashSet::new(),
+            keyword_starts: [false; 256],
+            unicode: false,
+            leading_dot_reals: false,
+            prev: None,
+            trace: None,
+        }
+        .comments(CommentSyntax::defaults())
+        .keywords(DEFAULT_KEYWORDS.iter().copied())
+    }
+
+    /// Accept Unicode identifiers, per UAX #31: an XID_Start character (or
+    /// `_`) followed by XID_Continue characters. Their lexemes are NFC
+    /// normalized, so differently-composed spellings of a name compare equal.
+    pub fn unicode_identifiers(mut self, on: bool) -> Self {
+        self.unicode = on;
+        self
+    }
+
+    /// Accept reals with no integer part, like `.5`. A `.` after an
+    /// identifier or closing bracket is still member access.
+    pub fn leading_dot_reals(mut self, on: bool) -> Self {
+        self.leading_dot_reals = on;
+        self
+    }
+
+    /// Replace the reserved words; anything else that lexes as an identifier
+    /// stays one.
+    pub fn keywords<S: Into<String>>(mut self, keywords: impl IntoIterator<Item = S>) -> Self {
+        self.keywords = keywords.into_iter().map(Into::into).collect();
+        self.keyword_starts = [false; 256];
+        for kw in &self.keywords {
+            if let Some(&b) = kw.as_bytes().first() {
+                self.keyword_starts[b as usize] = true;
+            }
+        }
+        self
+    }
+
+    /// Replace the comment forms the lexer recognizes.
+    ///
+    /// When two forms share a prefix, the one listed first wins.
+    pub fn comments(mut self, syntaxes: impl IntoIterator<Item = CommentSyntax>) -> Self {
+        self.comments = syntaxes.into_iter().collect();
+        self.comment_starts = [false; 256];
+        for syn in &self.comments {
+            self.comment_starts[syn.open().as_bytes()[0] as usize] = true;
+        }
+        self
+    }
+
+    /// Let block comments nest, so `/* a /* b */ c */` is one comment.
+    pub fn nested_comments(mut self, nested: bool) -> Self {
+        self.nested_comments = nested;
+        self
+    }
+
+    /// Yield comments as [`TokenType::Comment`] tokens instead of skipping
+    /// them.
+    pub fn keep_comments(mut self, keep: bool) -> Self {
+        self.keep_comments = keep;
+        self
+    }
+
+    /// Keep lexing after an error instead of stopping.
+    ///
+    /// The broken text is skipped up to the next whitespace, separator or
+    /// operator and yielded as a [`TokenType::Error`] token (an illegal
+    /// character is one by itself, without skipping); the error itself
+    /// is recorded and available from [`Lexer::errors`]. In this mode the
+    /// iterator never yields `Err`.
+    pub fn recovering(mut self) -> Self {
+        self.recover = true;
+        self
+    }
+
+    /// Call `f` with every move of the automaton and every token and error
+    /// handed out, in order.
+    pub fn trace(mut self, f: impl FnMut(TraceEvent) + 'src) -> Self {
+        self.trace = Some(Box::new(f));
+        self
+    }
+
+    // Report an event, if anyone's listening
+    fn emit(&mut self, event: impl FnOnce() -> TraceEvent) {
+        if let Some(f) = &mut self.trace {
+            f(event());
+        }
+    }
+
+    // Report each character between `from` and here, scanned without the
+    // automaton as part of a string or comment
+    fn scanned(&mut self, mut from: Cursor<'src>, within: TokenType) {
+        let Some(f) = &mut self.trace else { return };
+        while from.offset < self.chas.offset {
+            let start = from.here();
+            if let Some(c) = from.next() {
+                f(TraceEvent::Scan { span: from.to(start), c, within });
+            }
+        }
+    }
+
+    /// Errors recovered from so far. Always empty unless recovering.
+    pub fn errors(&self) -> &[LexerError] {
+        &self.errors
+    }
+
+    /// Lex the whole input, returning every token along with every error.
+    pub fn tokenize(mut self) -> (Vec<Token<'src>>, Vec<LexerError>) {
+        self.recover = true;
+        let toks = self.by_ref().filter_map(Result::ok).collect();
+        (toks, self.errors)
+    }
+
+    // Skip the rest of the broken token and turn it into an Error token
+    fn resync(&mut self, e: LexerError) -> Token<'src> {
+        // An illegal character is broken all by itself, and whatever follows
+        // it is lexed as usual
+        if !matches!(e, LexerError::IllegalCharacter(..)) {
+            while let Some(c) = self.chas.peek() {
+                if is_sync(c) {
+                    break;
+                }
+                self.chas.next();
+            }
+        }
+        // Never hand back an empty Error token, or we'd stall on it forever
+        if self.chas.offset == self.start.start {
+            self.chas.next();
+        }
+        self.errors.push(e);
+        let span = self.chas.to(self.start);
+        Token {
+            ty: TokenType::Error,
+            lex: Cow::Borrowed(self.chas.slice(span)),
+            span,
+            value: None,
+        }
+    }
+
+    // This isn't a full C lexer by any means - we don't handle many types of tokens
+    // Howveer, there is a bit more functionality than is actually required for the input
+    // The automaton itself lives in table.rs; this just runs it and acts on
+    // what it accepts
+    fn lex(&mut self) -> Option<Result<Token<'src>, LexerError>> {
+        let dfa = table::builtin();
+        loop {
+            let start = self.chas.here();
+            self.start = start;
+            let b = self.chas.peek_byte()?;
+            // Most bytes can't start a comment, and ruling that out up front
+            // saves trying every form
+            let syn = match self.comment_starts[b as usize] {
+                true => comment::starting_at(&self.chas, &self.comments),
+                false => None,
+            };
+            if let Some(syn) = syn {
+                let from = self.chas.clone();
+                let skipped = comment::skip(&mut self.chas, syn, self.nested_comments);
+                self.scanned(from, TokenType::Comment);
+                let span = match skipped {
+                    Ok(span) => span,
+                    Err(e) => return Some(Err(e)),
+                };
+                if self.keep_comments {
+                    return Some(Ok(Token {
+                        ty: TokenType::Comment,
+                        lex: Cow::Borrowed(self.chas.slice(span)),
+                        span,
+                        value: None,
+                    }));
+                }
+                self.emit(|| TraceEvent::SkippedComment(span));
+                continue;
+            }
+            if b == b'"' {
+                let from = self.chas.clone();
+                let res = string(&mut self.chas, start);
+                self.scanned(from, TokenType::StringLiteral);
+                return Some(res);
+            }
+            // Member access only makes sense on something with members. A
+            // recovered Error token stands in for a broken operand, so it
+            // counts too
+            let state = match self.prev {
+                Some(
+                    TokenType::Identifier
+                    | TokenType::Error
+                    | TokenType::Separator(Separator::RParen | Separator::RBracket | Separator::RBrace),
+                ) => States::StartMember,
+                _ if self.leading_dot_reals => States::StartLeadingDot,
+                _ => States::Start,
+            };
+            let unicode = self.unicode;
+            let class_of = |c| classify(dfa, c, unicode);
+            let rest = self.chas.rest();
+            let m = match &mut self.trace {
+                Some(f) => traced(dfa, state, start, rest, unicode, f),
+                None => run(dfa, state, rest)
+                    .or_else(|| dfa.longest_match_bytes(state as StateId, rest, class_of)),
+            };
+            let (acc, len) = match m {
+                Some((acc, len)) if len > 0 => (acc, len - acc.trailing()),
+                // Every class leads somewhere from the start states, so
+                // this means the table is broken
+                _ => {
+                    self.chas.next();
+                    return Some(Err(LexerError::InternalStateError));
+                }
+            };
+            self.chas.advance(len);
+            if acc == Accept::Whitespace {
+                continue;
+            }
+            let span = self.chas.to(start);
+            let lexeme = self.chas.slice(span);
+            let keyword = |lexeme: &str| {
+                self.keyword_starts[lexeme.as_bytes()[0] as usize] && self.keywords.contains(lexeme)
+            };
+            return Some(basic(acc, lexeme, span, keyword));
+        }
+    }
+}
+
+impl<'src> Iterator for Lexer<'src> {
+    type Item = Result<Token<'src>, LexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let res = self.lex();
+        match &res {
+            Some(Ok(tok)) => self.emit(|| TraceEvent::Token(tok.clone().into_owned())),
+            Some(Err(e)) => self.emit(|| TraceEvent::Error(e.clone())),
+            None => {}
+        }
+        match res {
+            Some(Err(e)) if self.recover => {
+                let tok = self.resync(e);
+                self.emit(|| TraceEvent::Token(tok.clone().into_owned()));
+                self.prev = Some(tok.ty);
+                Some(Ok(tok))
+            }
+            Some(Ok(tok)) => {
+                if tok.ty != TokenType::Comment {
+                    self.prev = Some(tok.ty);
+                }
+                Some(Ok(tok))
+            }
+            res => {
+                self.done = true;
+                res
+            }
+        }
+    }
+}
+
+impl std::iter::FusedIterator for Lexer<'_> {}
+
+// Double-quoted string, C-style escapes; can't span lines. As in Rust,
+// `\xNN` only goes up to 0x7f, so it can't make half a UTF-8 character
+fn string<'src>(chas: &mut Cursor<'src>, start: Span) -> Result<Token<'src>, LexerError> {
+    chas.next();
+    let quote = chas.to(start);
+    let body = chas.here();
+    // Borrowed until the first escape, since only escapes make the value
+    // differ from the source text
+    let mut value: Option<String> = None;
+    let mut bad = None;
+    loop {
+        // Jump over plain text to whatever comes next that matters
+        let rest = chas.rest();
+        let run = memchr3(b'"', b'\\', b'\n', rest.as_bytes()).unwrap_or(rest.len());
+        if let Some(value) = &mut value {
+            value.push_str(&rest[..run]);
+        }
+        chas.advance(run);
+        let at = chas.here();
+        match chas.peek_byte() {
+            None | Some(b'\n') => return Err(LexerError::UnterminatedString(quote)),
+            Some(b'"') => {
+                chas.next();
+                break;
+            }
+            _ => {
+                let value = value.get_or_insert_with(|| chas.slice(chas.to(body)).to_string());
+                chas.next();
+                match escape(chas, at) {
+                    Ok(c) => value.push(c),
+                    // Keep going to the closing quote so recovery resumes after it
+                    Err(e) => {
+                        bad.get_or_insert(e);
+                    }
+                }
+            }
+        }
+    }
+    if let Some(e) = bad {
+        return Err(e);
+    }
+    let span = chas.to(start);
+    let lex = chas.slice(span);
+    let value = match value {
+        Some(value) => Cow::Owned(value),
+        // Everything between the quotes
+        None => Cow::Borrowed(&lex[1..lex.len() - 1]),
+    };
+    Ok(Token {
+        ty: TokenType::StringLiteral,
+        lex: Cow::Borrowed(lex),
+        span,
+        value: Some(Literal::Str(value)),
+    })
+}
+
+// Decode the escape after a backslash; `at` is the backslash
+fn escape(chas: &mut Cursor, at: Span) -> Result<char, LexerError> {
+    let c = match chas.peek() {
+        Some('\n') | None => return Err(LexerError::InvalidEscape(chas.to(at))),
+        Some(c) => c,
+    };
+    chas.next();
+    let code = match c {
+        'n' => return Ok('\n'),
+        't' => return Ok('\t'),
+        '\\' => return Ok('\\'),
+        '"' => return Ok('"'),
+        'x' => hex_digits(chas, 2, 2).filter(|&code| code < 0x80),
+        'u' if chas.peek() == Some('{') => {
+            chas.next();
+            let code = hex_digits(chas, 1, 6);
+            if chas.peek() == Some('}') {
+                chas.next();
+                code
+            } else {
+                None
+            }
+        }
+        _ => None,
+    };
+    code.and_then(char::from_u32)
+        .ok_or_else(|| LexerError::InvalidEscape(chas.to(at)))
+}
+
+// Between `min` and `max` hex digits as a number
+fn hex_digits(chas: &mut Cursor, min: usize, max: usize) -> Option<u32> {
+    let mut code = 0;
+    let mut n = 0;
+    while n < max {
+        match chas.peek().and_then(|c| c.to_digit(16)) {
+            Some(d) => {
+                code = code * 16 + d;
+                n += 1;
+                chas.next();
+            }
+            None => break,
+        }
+    }
+    (n >= min).then_some(code)
+}
+
+// Turn an accepted match into a token, or the error it stands for
+fn basic<'src>(
+    acc: Accept,
+    lexeme: &'src str,
+    span: Span,
+    is_keyword: impl Fn(&str) -> bool,
+) -> Result<Token<'src>, LexerError> {
+    let ty = match acc {
+        Accept::Whitespace => return Err(LexerError::InternalStateError),
+        Accept::Identifier => {
+            // Only Unicode identifiers can be non-ASCII, and those get normalized
+            let nfc = lexeme.is_ascii() || is_nfc_quick(lexeme.chars()) == IsNormalized::Yes;
+            let lexeme: Cow<str> = match nfc {
+                true => Cow::Borrowed(lexeme),
+                false => Cow::Owned(lexeme.nfc().collect()),
+            };
+            return Ok(Token {
+                ty: match is_keyword(&lexeme) {
+                    true => TokenType::Keyword,
+                    false => TokenType::Identifier,
+                },
+                lex: lexeme,
+                span,
+                value: None,
+            });
+        }
+        Accept::Integer(radix) | Accept::IntegerBeforeRange(radix) => {
+            let digits = match radix {
+                Radix::Decimal => lexeme,
+                _ => &lexeme[2..],
+            };
+            let n = u64::from_str_radix(&without_underscores(digits), radix.value())
+                .map_err(|_| LexerError::IntegerOverflow(span))?;
+            return Ok(Token {
+                ty: TokenType::Number,
+                lex: Cow::Borrowed(lexeme),
+                span,
+                value: Some(Literal::Int { value: n, radix }),
+            });
+        }
+        Accept::Real => {
+            // Far too many digits parses to infinity rather than failing
+            let x = without_underscores(lexeme)
+                .parse::<f64>()
+                .ok()
+                .filter(|x| x.is_finite())
+                .ok_or(LexerError::InvalidReal(span))?;
+            return Ok(Token {
+                ty: TokenType::Real,
+                lex: Cow::Borrowed(lexeme),
+                span,
+                value: Some(Literal::Real(x)),
+            });
+        }
+        Accept::Operator(op) => TokenType::Operator(op),
+        Accept::Separator(sep) => TokenType::Separator(sep),
+        Accept::IllegalDot => return Err(LexerError::IllegalDot(last_char(lexeme, span))),
+        Accept::InvalidIdentifier => return Err(LexerError::InvalidIdentifier(span)),
+        Accept::InvalidDigit => return Err(LexerError::InvalidDigit(last_char(lexeme, span))),
+        Accept::MissingDigits => return Err(LexerError::MissingDigits(span)),
+        Accept::MissingExponent => return Err(LexerError::MissingExponent(span)),
+        Accept::IllegalCharacter => {
+            let c = lexeme.chars().next().unwrap_or_default();
+            return Err(LexerError::IllegalCharacter(c, span));
+        }
+    };
+    Ok(Token {
+        ty,
+        lex: Cow::Borrowed(lexeme),
+        span,
+        value: None,
+    })
+}
+
+// Digit separators dropped, copying only if there are any
+fn without_underscores(digits: &str) -> Cow<'_, str> {
+    match digits.contains('_') {
+        true => Cow::Owned(digits.replace('_', "")),
+        false => Cow::Borrowed(digits),
+    }
+}
+
+// Span of just the last character of a single-line match
+fn last_char(lexeme: &str, span: Span) -> Span {
+    let (i, _) = lexeme.char_indices().last().unwrap_or_default();
+    Span {
+        start: span.start + i,
+        col: span.col + lexeme[..i].chars().count(),
+        ..span
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Operator;
+
+    fn span(start: usize, end: usize, line: usize, col: usize) -> Span {
+        Span { start, end, line, col }
+    }
+
+    // Every token as (type, lexeme), and the errors recovered from
+    fn recover(src: &str) -> (Vec<(TokenType, String)>, Vec<LexerError>) {
+        let mut lexer = Lexer::new(src).recovering();
+        let toks = lexer
+            .by_ref()
+            .map(|tok| {
+                let tok = tok.expect("a recovering lexer yielded an error");
+                (tok.ty, tok.lex.into_owned())
+            })
+            .collect();
+        (toks, lexer.errors().to_vec())
+    }
+
+    fn tok(ty: TokenType, lex: &str) -> (TokenType, String) {
+        (ty, lex.to_string())
+    }
+
+    #[test]
+    fn error_tokens_run_to_the_next_sync_point() {
+        // The automaton stops at `a`; the Error token takes the rest
+        let (toks, errors) = recover("12ab + c");
+        assert_eq!(
+            toks,
+            [
+                tok(TokenType::Error, "12ab"),
+                tok(TokenType::Operator(Operator::Plus), "+"),
+                tok(TokenType::Identifier, "c"),
+            ]
+        );
+        assert_eq!(errors, [LexerError::InvalidIdentifier(span(0, 3, 1, 1))]);
+    }
+
+    #[test]
+    fn illegal_characters_are_errors_alone() {
+        let (toks, errors) = recover("x@y");
+        assert_eq!(
+            toks,
+            [tok(TokenType::Identifier, "x"), tok(TokenType::Error, "@"), tok(TokenType::Identifier, "y")]
+        );
+        assert_eq!(errors, [LexerError::IllegalCharacter('@', span(1, 2, 1, 2))]);
+        // A lone `!` or `$` isn't an operator or separator either
+        for src in ["a!b", "a$b", "aéb"] {
+            let (toks, errors) = recover(src);
+            assert_eq!(toks.len(), 3, "{}", src);
+            assert_eq!((&*toks[2].1, errors.len()), ("b", 1), "{}", src);
+        }
+    }
+
+    #[test]
+    fn member_access_after_a_broken_operand() {
+        let (toks, errors) = recover("12ab.len");
+        assert_eq!(
+            toks,
+            [
+                tok(TokenType::Error, "12ab"),
+                tok(TokenType::Operator(Operator::Dot), "."),
+                tok(TokenType::Identifier, "len"),
+            ]
+        );
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn ranges_in_any_radix() {
+        let values: Vec<_> = Lexer::new("0x1F..0b11").map(|tok| tok.unwrap().value).collect();
+        assert_eq!(
+            values,
+            [
+                Some(Literal::Int { value: 31, radix: Radix::Hexadecimal }),
+                None,
+                Some(Literal::Int { value: 3, radix: Radix::Binary }),
+            ]
+        );
+    }
+
+    #[test]
+    fn bad_escapes_spoil_the_whole_string() {
+        let (toks, errors) = recover(r#""a\qb" x"#);
+        assert_eq!(toks, [tok(TokenType::Error, r#""a\qb""#), tok(TokenType::Identifier, "x")]);
+        assert_eq!(errors, [LexerError::InvalidEscape(span(2, 4, 1, 3))]);
+    }
+
+    #[test]
+    fn unterminated_comments_take_the_rest() {
+        let (toks, errors) = recover("a /* b\nc");
+        assert_eq!(toks, [tok(TokenType::Identifier, "a"), tok(TokenType::Error, "/* b\nc")]);
+        assert_eq!(errors, [LexerError::UnterminatedComment(span(2, 4, 1, 3))]);
+    }
+
+    #[test]
+    fn every_error_is_collected() {
+        let src = "x = 0x + 1e;\ny = 0b12 + 1.2.3;";
+        let (toks, errors) = recover(src);
+        assert_eq!(
+            errors,
+            [
+                LexerError::MissingDigits(span(4, 6, 1, 5)),
+                LexerError::MissingExponent(span(9, 11, 1, 10)),
+                LexerError::InvalidDigit(span(20, 21, 2, 8)),
+                LexerError::IllegalDot(span(27, 28, 2, 15)),
+            ]
+        );
+        assert_eq!(toks.iter().filter(|(ty, _)| *ty == TokenType::Error).count(), errors.len());
+        // tokenize recovers the same way
+        let (tokenized, tokenize_errors) = Lexer::new(src).tokenize();
+        assert_eq!(tokenized.len(), toks.len());
+        assert_eq!(tokenize_errors, errors);
+    }
+
+    // The decoded value of the string literal `src` starts with
+    fn string_value(src: &str) -> Result<Cow<'_, str>, LexerError> {
+        match Lexer::new(src).next().expect("no token")?.value {
+            Some(Literal::Str(value)) => Ok(value),
+            value => panic!("not a string: {:?}", value),
+        }
+    }
+
+    #[test]
+    fn strings_borrow_until_an_escape() {
+        assert!(matches!(string_value(r#""plain text""#), Ok(Cow::Borrowed("plain text"))));
+        assert!(matches!(string_value(r#""""#), Ok(Cow::Borrowed(""))));
+        let value = string_value(r#""a\n\t\\\"b""#).unwrap();
+        assert!(matches!(value, Cow::Owned(_)));
+        assert_eq!(value, "a\n\t\\\"b");
+    }
+
+    #[test]
+    fn hex_and_unicode_escapes() {
+        assert_eq!(string_value(r#""\x41\x7f""#).unwrap(), "A\x7f");
+        assert_eq!(string_value(r#""\u{e9}\u{1F980}""#).unwrap(), "é🦀");
+        assert_eq!(string_value(r#""\u{10FFFF}""#).unwrap(), "\u{10FFFF}");
+        // What's after an escape is copied too
+        assert_eq!(string_value(r#""<\x41>""#).unwrap(), "<A>");
+    }
+
+    #[test]
+    fn invalid_escapes_cover_the_escape() {
+        let invalid = |src: &str, start: usize, end: usize| {
+            assert_eq!(
+                string_value(src),
+                Err(LexerError::InvalidEscape(span(start, end, 1, start + 1))),
+                "{}",
+                src
+            );
+        };
+        invalid(r#""\q""#, 1, 3);
+        // Past ASCII, which Rust doesn't allow either
+        invalid(r#""\x80""#, 1, 5);
+        invalid(r#""\xff""#, 1, 5);
+        invalid(r#""\x4""#, 1, 4);
+        invalid(r#""\xg0""#, 1, 3);
+        // Past the last code point, and surrogates
+        invalid(r#""\u{110000}""#, 1, 11);
+        invalid(r#""\u{D800}""#, 1, 9);
+        invalid(r#""\u{}""#, 1, 5);
+        // Seven digits: the escape ends after six, which aren't closed
+        invalid(r#""\u{1234567}""#, 1, 10);
+        invalid(r#""\u41""#, 1, 3);
+        invalid(r#""ok \q""#, 4, 6);
+    }
+
+    #[test]
+    fn unterminated_strings_point_at_the_quote() {
+        let unterminated = Err(LexerError::UnterminatedString(span(2, 3, 1, 3)));
+        assert_eq!(string_value(r#"  "abc"#), unterminated);
+        assert_eq!(string_value("  \"ab\ncd\""), unterminated);
+        assert_eq!(string_value(r#"  "ab\"#), unterminated);
+    }
+
+    #[test]
+    fn traces_every_character() {
+        let src = "\"ab\" /* c */ x // d\n[* e *] \"\\n\" + 1";
+        let mut traced = vec![false; src.len()];
+        let mut skipped = Vec::new();
+        Lexer::new(src)
+            .trace(|e| match e {
+                TraceEvent::Step { span, next, .. } if next != States::Dead => traced[span.start] = true,
+                TraceEvent::Scan { span, .. } => traced[span.start] = true,
+                TraceEvent::SkippedComment(span) => skipped.push(span.start),
+                _ => {}
+            })
+            .for_each(drop);
+        for (i, c) in src.char_indices() {
+            assert!(traced[i], "{:?} at {} not traced", c, i);
+        }
+        assert_eq!(skipped, [5, 15, 20]);
+    }
+
+    #[test]
+    fn recovery_never_yields_err_or_stalls() {
+        for src in ["", "@", "\"", "\"\\", "/*", "[* x", "1e+", "0x.", "..", "!", "$", "1..", "a.b.", "é"] {
+            let (toks, _) = recover(src);
+            // Each token takes something, so there can't be more than bytes
+            assert!(toks.len() <= src.len(), "{:?}", src);
+        }
+    }
+
+    #[test]
+    fn errors_end_the_input_without_recovery() {
+        let mut lexer = Lexer::new("12ab + c");
+        assert_eq!(lexer.next(), Some(Err(LexerError::InvalidIdentifier(span(0, 3, 1, 1)))));
+        assert_eq!(lexer.next(), None);
+        assert!(lexer.errors().is_empty());
+    }
+}
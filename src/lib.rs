@@ -0,0 +1,38 @@
+//! Lexer for Saloni Modi's CPSC 323 (Spring 2024).
+//!
+//! The main entry point is [`Lexer`], an iterator over the tokens of a
+//! source string:
+//!
+//! ```
+//! use cpsc323_lexer::{Lexer, TokenType};
+//!
+//! let toks: Vec<_> = Lexer::new("while (t < upper) s = 22.00;")
+//!     .collect::<Result<_, _>>()
+//!     .unwrap();
+//! assert_eq!(toks[0].ty, TokenType::Keyword);
+//! assert_eq!(toks[8].lex, "22.00");
+//! ```
+
+pub mod analysis;
+mod comment;
+mod cursor;
+pub mod derive;
+pub mod dfa;
+pub mod dot;
+pub mod equiv;
+mod error;
+mod lexer;
+pub mod nfa;
+pub mod regex;
+mod runs;
+mod scan;
+pub mod spec;
+pub mod table;
+mod token;
+mod trace;
+
+pub use comment::CommentSyntax;
+pub use error::LexerError;
+pub use lexer::{Lexer, DEFAULT_KEYWORDS};
+pub use token::{Literal, Operator, OwnedToken, Radix, Separator, Span, Token, TokenType};
+pub use trace::TraceEvent;
@@ -1,174 +1,601 @@
-use core::fmt;
-use std::{fmt::Debug, iter::Peekable, str::Chars, io::Write};
-
-#[derive(Debug)]
-enum States {
-    Start,
-    DefiningIdentifier,
-    DefiningInteger,
-    DefiningReal,
+use std::io::{Read, Write};
+use std::process::ExitCode;
+
+use cpsc323_lexer::dot::Dot;
+use cpsc323_lexer::spec::{Action, Spec};
+use cpsc323_lexer::{equiv, table, Lexer, DEFAULT_KEYWORDS, Literal, LexerError, Span, TraceEvent};
+
+const USAGE: &str = "\
+usage: cpsc323-lexer [OPTIONS] [INPUT]...
+
+Lex each INPUT (or stdin, if INPUT is `-` or none is given) and write the
+tokens out.
+
+options:
+  -o, --output FILE       write tokens to FILE instead of stdout (`-` is stdout)
+  -f, --format FMT        output format: table (default), tsv or json
+      --keep-comments     emit comments as tokens instead of skipping them
+      --nested-comments   let block comments nest
+      --unicode           accept Unicode (UAX #31) identifiers
+      --leading-dot-reals accept reals like `.5`
+      --keywords LIST     comma-separated reserved words, replacing the defaults
+      --keyword-file FILE read the reserved words from FILE, whitespace-separated
+      --spec FILE         lex with the rules in flex-style spec FILE instead of
+                          the built-in ones; the options above don't apply
+      --check             with --spec, report rules that overlap or can never
+                          match instead of lexing anything; exits 1 if any
+                          rule can never match
+      --verify            check the built-in lexer against the token regexes in
+                          the --spec (by default, the ones from the FSA PDF)
+                          and print a shortest counterexample for each token
+                          type they disagree on; exits 1 if any
+      --dot               write the lexer's automaton as a Graphviz digraph
+                          instead of lexing anything
+      --trace             write every move of the automaton (or character of
+                          a string or comment), and each token, error and
+                          skipped comment as it's produced, instead of the
+                          tokens
+  -h, --help              print this message
+
+With no arguments at all, reads input_scode.txt and writes output_file.txt,
+like the original assignment.
+
+exit status: 0 on success, 1 on lexical errors, 2 on bad usage, 3 on I/O errors,
+4 on a --spec file that doesn't parse";
+
+// The token regexes from "FSA Regex_Graphs.pdf", which --verify checks
+// against by default
+const FSA_SPEC: &str = include_str!("../fsa.spec");
+
+// Exit codes, so scripts can tell a bad input file from a bad program
+const EXIT_LEXICAL: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+const EXIT_IO: u8 = 3;
+const EXIT_SPEC: u8 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    // The `{:>10} = {}` layout the assignment asked for
+    Table,
+    Tsv,
+    // One JSON object per token per line
+    Json,
+}
+
+struct Args {
+    inputs: Vec<String>,
+    output: String,
+    format: Format,
+    keep_comments: bool,
+    nested_comments: bool,
+    keywords: Option<Vec<String>>,
+    keyword_file: Option<String>,
+    unicode: bool,
+    leading_dot_reals: bool,
+    spec: Option<String>,
+    check: bool,
+    verify: bool,
+    dot: bool,
+    trace: bool,
 }
 
-struct Token {
-    pub ty: TokenType,
-    pub lex: String,
+fn parse_args(mut argv: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut args = Args {
+        inputs: Vec::new(),
+        output: String::from("-"),
+        format: Format::Table,
+        keep_comments: false,
+        nested_comments: false,
+        keywords: None,
+        keyword_file: None,
+        unicode: false,
+        leading_dot_reals: false,
+        spec: None,
+        check: false,
+        verify: false,
+        dot: false,
+        trace: false,
+    };
+    let mut any = false;
+    let mut positional_only = false;
+    while let Some(arg) = argv.next() {
+        any = true;
+        if positional_only || arg == "-" || !arg.starts_with('-') {
+            args.inputs.push(arg);
+            continue;
+        }
+        // Accept both `--output FILE` and `--output=FILE`
+        let (flag, inline) = match arg.split_once('=') {
+            Some((f, v)) if arg.starts_with("--") => (f.to_string(), Some(v.to_string())),
+            _ => (arg, None),
+        };
+        let mut value = |name: &str| {
+            inline
+                .clone()
+                .or_else(|| argv.next())
+                .ok_or_else(|| format!("{} needs a value", name))
+        };
+        match flag.as_str() {
+            "--" => positional_only = true,
+            "-h" | "--help" => return Err(String::new()),
+            "-o" | "--output" => args.output = value("--output")?,
+            "-f" | "--format" => {
+                args.format = match value("--format")?.as_str() {
+                    "table" => Format::Table,
+                    "tsv" => Format::Tsv,
+                    "json" => Format::Json,
+                    other => return Err(format!("unknown format `{}`", other)),
+                }
+            }
+            "--keep-comments" => args.keep_comments = true,
+            "--nested-comments" => args.nested_comments = true,
+            "--unicode" => args.unicode = true,
+            "--leading-dot-reals" => args.leading_dot_reals = true,
+            "--keywords" => {
+                let list = value("--keywords")?;
+                args.keywords = Some(list.split(',').filter(|k| !k.is_empty()).map(String::from).collect());
+            }
+            "--keyword-file" => args.keyword_file = Some(value("--keyword-file")?),
+            "--spec" => args.spec = Some(value("--spec")?),
+            "--check" => args.check = true,
+            "--verify" => args.verify = true,
+            "--dot" => args.dot = true,
+            "--trace" => args.trace = true,
+            _ => return Err(format!("unknown option `{}`", flag)),
+        }
+    }
+    if args.check && args.spec.is_none() {
+        return Err(String::from("--check needs a --spec to check"));
+    }
+    if args.trace && args.spec.is_some() {
+        return Err(String::from("--trace only works with the built-in lexer"));
+    }
+    if !any {
+        args.inputs.push(String::from("input_scode.txt"));
+        args.output = String::from("output_file.txt");
+    } else if args.inputs.is_empty() {
+        args.inputs.push(String::from("-"));
+    }
+    Ok(args)
 }
 
-#[derive(Debug)]
-enum TokenType {
-    Identifier,
-    Number,
-    Real,
-    Separator,
-    Operator,
-    Keyword,
+fn read_input(name: &str) -> std::io::Result<String> {
+    if name == "-" {
+        let mut s = String::new();
+        std::io::stdin().read_to_string(&mut s)?;
+        Ok(s)
+    } else {
+        std::fs::read_to_string(name)
+    }
 }
 
-// If a LexerError is returned, the rest of the string should be considered void
-#[derive(Debug, PartialEq)]
-enum LexerError {
-    IllegalDot,
-    InternalStateError,
-    InvalidIdentifier,
-    Eof
+fn open_output(name: &str) -> std::io::Result<Box<dyn Write>> {
+    Ok(if name == "-" {
+        Box::new(std::io::BufWriter::new(std::io::stdout().lock()))
+    } else {
+        Box::new(std::io::BufWriter::new(std::fs::File::create(name)?))
+    })
 }
 
-impl std::fmt::Display for LexerError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        Debug::fmt(&self, f)
+fn json_str(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
     }
+    out.push('"');
+    out
 }
 
-impl std::error::Error for LexerError {}
-
-// This isn't a full C lexer by any means - we don't handle many types of tokens
-// For instance, ident.ident is not handled here
-// We also don't handle C string syntax (oh no)
-// Howveer, there is a bit more functionality than is actually required for the input
-fn lexer(chas: &mut Peekable<Chars>) -> Result<Token, LexerError> {
-    let mut st = States::Start;
-    let mut lexeme = String::new();
-    loop {
-        let c = *(match chas.peek() {
-            Some(v) => v,
-            None => match st {
-                States::Start => return Err(LexerError::Eof),
-                _ => return Ok(basic(st, lexeme)?)
-            },
-        });
+// Only what would break a TSV row is escaped; other characters, like those
+// in Unicode identifiers, pass through
+fn tsv_str(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars() {
         match c {
-            ' ' | '\t' | '\r' | '\n' => {
-                chas.next();
-                match st {
-                    States::Start => {}
-                    _ => return Ok(basic(st, lexeme)?)
-                }
-            }
-            '.' => match st {
-                States::DefiningInteger => {
-                    st = States::DefiningReal;
-                    lexeme.push('.');
-                    chas.next();
-                }
-                _ => return Err(LexerError::IllegalDot),
-            },
-            '(' | ')' | ';' => {
-                match st {
-                    States::Start => {
-                        chas.next();
-                        return Ok(Token {
-                            ty: TokenType::Separator,
-                            lex: c.to_string()
-                        })
-                    },
-                    _ => return Ok(basic(st, lexeme)?)
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn write_token(
+    f: &mut dyn Write,
+    fmt: Format,
+    file: &str,
+    ty: &str,
+    lex: &str,
+    span: Span,
+    value: Option<&Literal>,
+) -> std::io::Result<()> {
+    match fmt {
+        Format::Table => writeln!(f, "{:>10} = {}", ty, lex),
+        Format::Tsv => writeln!(
+            f,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            file,
+            ty,
+            tsv_str(lex),
+            span.line,
+            span.col,
+            span.start,
+            span.end
+        ),
+        Format::Json => {
+            let value = match value {
+                Some(Literal::Str(v)) => format!(",\"value\":{}", json_str(v)),
+                Some(Literal::Int { value, radix }) => {
+                    format!(",\"value\":{},\"radix\":{}", value, radix.value())
                 }
+                // {:?} keeps the decimal point on whole numbers
+                Some(Literal::Real(x)) => format!(",\"value\":{:?}", x),
+                _ => String::new(),
+            };
+            writeln!(
+                f,
+                "{{\"file\":{},\"type\":{},\"lexeme\":{},\"line\":{},\"col\":{},\"start\":{},\"end\":{}{}}}",
+                json_str(file),
+                json_str(ty),
+                json_str(lex),
+                span.line,
+                span.col,
+                span.start,
+                span.end,
+                value
+            )
+        }
+    }
+}
+
+fn write_event(f: &mut dyn Write, fmt: Format, file: &str, event: &TraceEvent) -> std::io::Result<()> {
+    match (fmt, event) {
+        (Format::Table, TraceEvent::Step { span, c, state, class, next }) => writeln!(
+            f,
+            "{:>9}  {:<6} {:<22} {:<16} {:?}",
+            span.to_string(),
+            format!("{:?}", c),
+            format!("{:?}", state),
+            format!("{:?}", class),
+            next
+        ),
+        (Format::Table, TraceEvent::Scan { span, c, within }) => {
+            writeln!(f, "{:>9}  {:<6} in {}", span.to_string(), format!("{:?}", c), within)
+        }
+        (Format::Table, TraceEvent::SkippedComment(span)) => {
+            writeln!(f, "{:>9}  -- skipped comment", span.to_string())
+        }
+        (Format::Table, TraceEvent::Token(tok)) => {
+            writeln!(f, "{:>9}  => {} {:?}", tok.span.to_string(), tok.ty, tok.lex)
+        }
+        (Format::Table, TraceEvent::Error(e)) => {
+            let at = e.span().map(|s| s.to_string()).unwrap_or_default();
+            writeln!(f, "{:>9}  !! {}", at, e)
+        }
+        (Format::Tsv, TraceEvent::Step { span, c, state, class, next }) => writeln!(
+            f,
+            "{}\tstep\t{}\t{}\t{}\t{}\t{:?}\t{:?}\t{:?}",
+            file,
+            span.line,
+            span.col,
+            span.start,
+            tsv_str(&c.to_string()),
+            state,
+            class,
+            next
+        ),
+        (Format::Tsv, TraceEvent::Scan { span, c, within }) => writeln!(
+            f,
+            "{}\tscan\t{}\t{}\t{}\t{}\t{}",
+            file,
+            span.line,
+            span.col,
+            span.start,
+            tsv_str(&c.to_string()),
+            within.name()
+        ),
+        (Format::Tsv, TraceEvent::SkippedComment(span)) => writeln!(
+            f,
+            "{}\tskip\t{}\t{}\t{}\t{}",
+            file, span.line, span.col, span.start, span.end
+        ),
+        (Format::Tsv, TraceEvent::Token(tok)) => writeln!(
+            f,
+            "{}\ttoken\t{}\t{}\t{}\t{}\t{}",
+            file,
+            tok.span.line,
+            tok.span.col,
+            tok.span.start,
+            tok.ty.name(),
+            tsv_str(&tok.lex)
+        ),
+        (Format::Tsv, TraceEvent::Error(e)) => {
+            let at = match e.span() {
+                Some(span) => format!("{}\t{}\t{}", span.line, span.col, span.start),
+                None => String::from("\t\t"),
+            };
+            writeln!(f, "{}\terror\t{}\t{}", file, at, e)
+        }
+        (Format::Json, TraceEvent::Step { span, c, state, class, next }) => writeln!(
+            f,
+            "{{\"file\":{},\"event\":\"step\",\"line\":{},\"col\":{},\"start\":{},\"char\":{},\"state\":\"{:?}\",\"class\":\"{:?}\",\"next\":\"{:?}\"}}",
+            json_str(file),
+            span.line,
+            span.col,
+            span.start,
+            json_str(&c.to_string()),
+            state,
+            class,
+            next
+        ),
+        (Format::Json, TraceEvent::Scan { span, c, within }) => writeln!(
+            f,
+            "{{\"file\":{},\"event\":\"scan\",\"line\":{},\"col\":{},\"start\":{},\"char\":{},\"within\":{}}}",
+            json_str(file),
+            span.line,
+            span.col,
+            span.start,
+            json_str(&c.to_string()),
+            json_str(within.name())
+        ),
+        (Format::Json, TraceEvent::SkippedComment(span)) => writeln!(
+            f,
+            "{{\"file\":{},\"event\":\"skip\",\"line\":{},\"col\":{},\"start\":{},\"end\":{}}}",
+            json_str(file),
+            span.line,
+            span.col,
+            span.start,
+            span.end
+        ),
+        (Format::Json, TraceEvent::Token(tok)) => writeln!(
+            f,
+            "{{\"file\":{},\"event\":\"token\",\"line\":{},\"col\":{},\"start\":{},\"end\":{},\"type\":{},\"lexeme\":{}}}",
+            json_str(file),
+            tok.span.line,
+            tok.span.col,
+            tok.span.start,
+            tok.span.end,
+            json_str(tok.ty.name()),
+            json_str(&tok.lex)
+        ),
+        (Format::Json, TraceEvent::Error(e)) => {
+            let at = match e.span() {
+                Some(span) => format!(
+                    ",\"line\":{},\"col\":{},\"start\":{}",
+                    span.line, span.col, span.start
+                ),
+                None => String::new(),
+            };
+            writeln!(
+                f,
+                "{{\"file\":{},\"event\":\"error\"{},\"message\":{}}}",
+                json_str(file),
+                at,
+                json_str(&e.to_string())
+            )
+        }
+        _ => Ok(()),
+    }
+}
+
+fn run(args: &Args, spec: Option<Spec>) -> Result<bool, (String, std::io::Error)> {
+    if let (true, Some(spec), Some(file)) = (args.check, &spec, &args.spec) {
+        let mut f = open_output(&args.output).map_err(|e| (args.output.clone(), e))?;
+        return check(&mut f, file, spec)
+            .and_then(|ok| f.flush().map(|_| ok))
+            .map_err(|e| (args.output.clone(), e));
+    }
+    if args.dot {
+        let mut f = open_output(&args.output).map_err(|e| (args.output.clone(), e))?;
+        let res = match &spec {
+            Some(spec) => {
+                let dot = Dot::new(spec.dfa()).accept_labels(|r| match &spec.rules()[r].action {
+                    Action::Emit(kind) => kind.clone(),
+                    Action::Skip => String::from("skip"),
+                    Action::Error(msg) => format!("error: {}", msg),
+                });
+                write!(f, "{}", dot)
             }
-            '<' | '>' | '=' => {
-                match st {
-                    States::Start => {
-                        chas.next();
-                        return Ok(Token {
-                            ty: TokenType::Operator,
-                            lex: c.to_string()
-                        })
-                    },
-                    _ => return Ok(basic(st, lexeme)?)
-                }
+            None => write!(f, "{}", table::dot()),
+        };
+        res.and_then(|_| f.flush()).map_err(|e| (args.output.clone(), e))?;
+        return Ok(true);
+    }
+    let mut keywords = args.keywords.clone();
+    if let Some(file) = &args.keyword_file {
+        let list = std::fs::read_to_string(file).map_err(|e| (file.clone(), e))?;
+        keywords = Some(list.split_whitespace().map(String::from).collect());
+    }
+    if args.verify {
+        let spec = match spec {
+            Some(spec) => spec,
+            None => Spec::parse(FSA_SPEC).expect("fsa.spec is valid"),
+        };
+        let keywords = keywords.unwrap_or_else(|| DEFAULT_KEYWORDS.iter().map(|k| k.to_string()).collect());
+        let mut f = open_output(&args.output).map_err(|e| (args.output.clone(), e))?;
+        return verify(&mut f, &spec, &keywords)
+            .and_then(|ok| f.flush().map(|_| ok))
+            .map_err(|e| (args.output.clone(), e));
+    }
+    let mut f = open_output(&args.output).map_err(|e| (args.output.clone(), e))?;
+    let out_err = |e| (args.output.clone(), e);
+    let mut clean = true;
+    for (i, input) in args.inputs.iter().enumerate() {
+        let b = read_input(input).map_err(|e| (input.clone(), e))?;
+        if args.format == Format::Table && args.inputs.len() > 1 {
+            if i > 0 {
+                writeln!(f).map_err(out_err)?;
             }
-            '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => {
-                chas.next();
-                match st {
-                    States::Start => st = States::DefiningInteger,
-                    _ => {}
-                }
-                lexeme.push(c);
+            writeln!(f, "==> {} <==", input).map_err(out_err)?;
+        }
+        let errors = match &spec {
+            Some(spec) => lex_spec(&mut f, args, input, spec, &b).map_err(out_err)?,
+            None => lex_builtin(&mut f, args, input, keywords.as_deref(), &b).map_err(out_err)?,
+        };
+        for e in &errors {
+            eprintln!("{}: {}", input, e);
+        }
+        clean &= errors.is_empty();
+    }
+    f.flush().map_err(out_err)?;
+    Ok(clean)
+}
+
+// Write out the spec's conflicts, and whether none of its rules are dead
+fn check(f: &mut dyn Write, file: &str, spec: &Spec) -> std::io::Result<bool> {
+    let report = spec.check();
+    let rules = spec.rules();
+    for o in &report.overlaps {
+        let (winner, loser) = (&rules[o.rules.0], &rules[o.rules.1]);
+        writeln!(
+            f,
+            "{}:{}: `{}` overlaps line {} `{}` on {:?}; line {} wins",
+            file, loser.line, loser.pattern, winner.line, winner.pattern, o.witness, winner.line
+        )?;
+    }
+    for s in &report.shadowed {
+        let rule = &rules[s.rule];
+        let why = match &s.by[..] {
+            [] => String::from("it matches nothing"),
+            by => {
+                let lines: Vec<_> = by.iter().map(|&r| rules[r].line.to_string()).collect();
+                format!("shadowed by line {}", lines.join(", "))
             }
-            _ => {
-                chas.next();
-                lexeme.push(c);
-                match st {
-                    States::Start => st = States::DefiningIdentifier,
-                    States::DefiningIdentifier => {},
-                    _ => return Err(LexerError::InvalidIdentifier),
-                }
-            },
         };
+        writeln!(f, "{}:{}: `{}` can never match: {}", file, rule.line, rule.pattern, why)?;
     }
+    Ok(report.shadowed.is_empty())
 }
 
-fn basic(st: States, lexeme: String) -> Result<Token, LexerError> {
-    match st {
-        States::Start => return Err(LexerError::InternalStateError),
-        States::DefiningIdentifier => {
-            match lexeme.as_str() {
-                // expansion: add more keywords
-                "while" => {
-                    return Ok(Token {
-                        ty: TokenType::Keyword,
-                        lex: lexeme,
-                    })
-                }
-                _ => {
-                    return Ok(Token {
-                        ty: TokenType::Identifier,
-                        lex: lexeme,
-                    })
-                }
+// Write out where the lexer and the spec disagree, and whether they don't
+fn verify(f: &mut dyn Write, spec: &Spec, keywords: &[String]) -> std::io::Result<bool> {
+    let diffs = equiv::verify(spec, keywords);
+    // Every kind the spec emits, in order, whether or not they differ
+    let mut kinds: Vec<&str> = Vec::new();
+    for rule in spec.rules() {
+        if let Action::Emit(kind) = &rule.action {
+            if !kinds.contains(&kind.as_str()) {
+                kinds.push(kind);
+            }
+        }
+    }
+    for kind in kinds {
+        match diffs.iter().find(|d| d.kind == kind) {
+            Some(d) => {
+                let (yes, no) = match d.in_spec {
+                    true => ("the spec", "the lexer"),
+                    false => ("the lexer", "the spec"),
+                };
+                writeln!(
+                    f,
+                    "{}: {} lexes {:?} as one {} token, but {} doesn't",
+                    kind, yes, d.witness, kind, no
+                )?;
             }
+            None => writeln!(f, "{}: equivalent", kind)?,
         }
-        States::DefiningInteger => {
-            return Ok(Token {
-                ty: TokenType::Number,
-                lex: lexeme,
-            })
+    }
+    Ok(diffs.is_empty())
+}
+
+// Lex with the built-in lexer and write out the tokens (or the trace)
+fn lex_builtin(
+    f: &mut dyn Write,
+    args: &Args,
+    input: &str,
+    keywords: Option<&[String]>,
+    src: &str,
+) -> std::io::Result<Vec<LexerError>> {
+    let mut lexer = Lexer::new(src)
+        .keep_comments(args.keep_comments)
+        .nested_comments(args.nested_comments)
+        .unicode_identifiers(args.unicode)
+        .leading_dot_reals(args.leading_dot_reals);
+    if let Some(kws) = keywords {
+        lexer = lexer.keywords(kws);
+    }
+    if !args.trace {
+        let (toks, errors) = lexer.tokenize();
+        for tok in &toks {
+            write_token(f, args.format, input, tok.ty.name(), &tok.lex, tok.span, tok.value.as_ref())?;
         }
-        States::DefiningReal => {
-            return Ok(Token {
-                ty: TokenType::Real,
-                lex: lexeme,
-            })
+        return Ok(errors);
+    }
+    if args.format == Format::Table {
+        writeln!(f, "{:>9}  {:<6} {:<22} {:<16} next", "at", "char", "state", "class")?;
+    }
+    // Write each event as it happens; a trace is several per input
+    // character, too many to hold on to
+    let mut written = Ok(());
+    let mut lexer = lexer.recovering().trace(|e| {
+        if written.is_ok() {
+            written = write_event(f, args.format, input, &e);
         }
+    });
+    lexer.by_ref().for_each(drop);
+    let errors = lexer.errors().to_vec();
+    drop(lexer);
+    written?;
+    Ok(errors)
+}
+
+// Lex with a spec's rules and write out the tokens
+fn lex_spec(
+    f: &mut dyn Write,
+    args: &Args,
+    input: &str,
+    spec: &Spec,
+    src: &str,
+) -> std::io::Result<Vec<LexerError>> {
+    let (toks, errors) = spec.lexer(src).tokenize();
+    for tok in &toks {
+        write_token(f, args.format, input, tok.kind, tok.lex, tok.span, None)?;
     }
+    Ok(errors)
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let b = std::fs::read_to_string("input_scode.txt")?;
-    let mut i = b.chars().peekable();
-    let mut f = std::io::BufWriter::new(std::fs::File::create("output_file.txt")?);
-    loop {
-        let val = lexer(&mut i);
-        match val {
-            Ok(tok) => writeln!(f, "{:>10} = {}", format!("{:?}", tok.ty), tok.lex),
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(a) => a,
+        Err(e) if e.is_empty() => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("cpsc323-lexer: {}\n\n{}", e, USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+    let spec = match &args.spec {
+        None => None,
+        Some(file) => match std::fs::read_to_string(file).map(|src| Spec::parse(&src)) {
+            Ok(Ok(spec)) => Some(spec),
+            Ok(Err(e)) => {
+                eprintln!("cpsc323-lexer: {}: {}", file, e);
+                return ExitCode::from(EXIT_SPEC);
+            }
             Err(e) => {
-                f.flush()?;
-                if e == LexerError::Eof {
-                    std::process::exit(0);
-                } else {
-                    eprintln!("Unacceptable error: {}", e);
-                    std::process::exit(1);
-                }
+                eprintln!("cpsc323-lexer: {}: {}", file, e);
+                return ExitCode::from(EXIT_IO);
             }
-        };
+        },
+    };
+    match run(&args, spec) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(EXIT_LEXICAL),
+        Err((file, e)) => {
+            eprintln!("cpsc323-lexer: {}: {}", file, e);
+            ExitCode::from(EXIT_IO)
+        }
     }
 }
@@ -0,0 +1,223 @@
+//! Thompson NFAs, and turning them into a [`Dfa`] by subset construction.
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::dfa::{Dfa, StateId, DEAD};
+use crate::regex::{CharSet, Regex, NON_ASCII, SYMBOLS};
+
+#[derive(Debug, Clone)]
+enum State {
+    // Epsilon moves
+    Split(Vec<usize>),
+    // One move on any character in the set
+    Char(CharSet, usize),
+    // The end of rule `n`'s pattern
+    Match(usize),
+}
+
+/// An NFA built from one or more rules by Thompson's construction.
+#[derive(Debug, Clone)]
+pub struct Nfa {
+    states: Vec<State>,
+    start: usize,
+}
+
+impl Nfa {
+    /// The NFA accepting any of `rules`, remembering which one matched.
+    /// Rule `n` ends in a match state labelled `n`.
+    pub fn from_rules(rules: &[Regex]) -> Nfa {
+        let mut nfa = Nfa {
+            states: vec![State::Split(Vec::new())],
+            start: 0,
+        };
+        let mut starts = Vec::new();
+        for (n, re) in rules.iter().enumerate() {
+            let end = nfa.push(State::Match(n));
+            starts.push(nfa.build(re, end));
+        }
+        nfa.states[0] = State::Split(starts);
+        nfa
+    }
+
+    pub fn num_states(&self) -> usize {
+        self.states.len()
+    }
+
+    fn push(&mut self, s: State) -> usize {
+        self.states.push(s);
+        self.states.len() - 1
+    }
+
+    // Build `re` so it continues to `next` once matched, and return its start.
+    // Building back to front means every fragment knows its exit up front.
+    fn build(&mut self, re: &Regex, next: usize) -> usize {
+        match re {
+            Regex::Empty => next,
+            Regex::Set(set) => self.push(State::Char(*set, next)),
+            Regex::Concat(parts) => parts.iter().rev().fold(next, |next, re| self.build(re, next)),
+            Regex::Alt(alts) => {
+                let starts = alts.iter().map(|re| self.build(re, next)).collect();
+                self.push(State::Split(starts))
+            }
+            Regex::Star(re) => {
+                let split = self.push(State::Split(Vec::new()));
+                let body = self.build(re, split);
+                self.states[split] = State::Split(vec![body, next]);
+                split
+            }
+            Regex::Plus(re) => {
+                let split = self.push(State::Split(Vec::new()));
+                let body = self.build(re, split);
+                self.states[split] = State::Split(vec![body, next]);
+                body
+            }
+            Regex::Opt(re) => {
+                let body = self.build(re, next);
+                self.push(State::Split(vec![body, next]))
+            }
+            Regex::Repeat(re, min, max) => {
+                let mut next = match max {
+                    None => self.build(&Regex::Star(re.clone()), next),
+                    // Each optional copy can skip straight to the end
+                    Some(max) => (*min..*max).fold(next, |rest, _| {
+                        let body = self.build(re, rest);
+                        self.push(State::Split(vec![body, next]))
+                    }),
+                };
+                for _ in 0..*min {
+                    next = self.build(re, next);
+                }
+                next
+            }
+        }
+    }
+
+    // Add `s` and everything reachable from it by epsilon moves
+    fn closure(&self, s: usize, set: &mut BTreeSet<usize>) {
+        let mut stack = vec![s];
+        while let Some(s) = stack.pop() {
+            if !set.insert(s) {
+                continue;
+            }
+            if let State::Split(next) = &self.states[s] {
+                stack.extend(next);
+            }
+        }
+    }
+
+    // The character sets on this NFA's moves, grouped into equivalence
+    // classes: two symbols share a class when every set treats them alike.
+    // Returns the class of each symbol and the number of classes.
+    fn classes(&self) -> ([u8; SYMBOLS], usize) {
+        let mut sets: Vec<CharSet> = Vec::new();
+        for s in &self.states {
+            if let State::Char(set, _) = s {
+                if !sets.contains(set) {
+                    sets.push(*set);
+                }
+            }
+        }
+        let mut ids: HashMap<Vec<bool>, u8> = HashMap::new();
+        let mut classes = [0; SYMBOLS];
+        for (sym, class) in classes.iter_mut().enumerate() {
+            let sig: Vec<bool> = sets.iter().map(|s| s.contains_symbol(sym)).collect();
+            let n = ids.len() as u8;
+            *class = *ids.entry(sig).or_insert(n);
+        }
+        (classes, ids.len())
+    }
+
+    /// Subset construction. Each DFA state accepts the lowest-numbered rule
+    /// among the NFA match states it contains.
+    pub fn to_dfa(&self) -> Dfa<usize> {
+        let (classes, num_classes) = self.classes();
+        // A symbol standing for each class
+        let mut sample = vec![0; num_classes];
+        for sym in (0..SYMBOLS).rev() {
+            sample[classes[sym] as usize] = sym;
+        }
+
+        let mut start = BTreeSet::new();
+        self.closure(self.start, &mut start);
+        // The empty set is the dead state
+        let mut ids: HashMap<BTreeSet<usize>, StateId> = HashMap::new();
+        ids.insert(BTreeSet::new(), DEAD);
+        ids.insert(start.clone(), 1);
+        let mut sets = vec![BTreeSet::new(), start];
+        let mut trans: Vec<(StateId, u8, StateId)> = Vec::new();
+        let mut todo = vec![1];
+        while let Some(id) = todo.pop() {
+            for (class, &sym) in sample.iter().enumerate() {
+                let mut next = BTreeSet::new();
+                for &s in &sets[id] {
+                    if let State::Char(set, to) = &self.states[s] {
+                        if set.contains_symbol(sym) {
+                            self.closure(*to, &mut next);
+                        }
+                    }
+                }
+                let to = match ids.get(&next) {
+                    Some(&to) => to,
+                    None => {
+                        let to = sets.len();
+                        ids.insert(next.clone(), to);
+                        sets.push(next);
+                        todo.push(to);
+                        to
+                    }
+                };
+                trans.push((id, class as u8, to));
+            }
+        }
+
+        let mut ascii = [0; 128];
+        ascii.copy_from_slice(&classes[..128]);
+        let mut dfa = Dfa::new(ascii, classes[NON_ASCII], num_classes, sets.len(), 1);
+        for (from, class, to) in trans {
+            dfa.set_transition(from, class, to);
+        }
+        for (id, set) in sets.iter().enumerate() {
+            let rule = set
+                .iter()
+                .filter_map(|&s| match self.states[s] {
+                    State::Match(n) => Some(n),
+                    _ => None,
+                })
+                .min();
+            dfa.set_accept(id, rule);
+        }
+        dfa
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subset_construction() {
+        let nfa = Nfa::from_rules(&[Regex::parse("(a|b)*abb").unwrap()]);
+        let dfa = nfa.to_dfa();
+        assert!(nfa.num_states() > dfa.num_states());
+        let run = |s: &str| dfa.longest_match(dfa.start(), s, |c| dfa.class_of(c));
+        assert_eq!(run("babb"), Some((0, 4)));
+        assert_eq!(run("abab"), None);
+    }
+
+    #[test]
+    fn lowest_rule_wins() {
+        let rules = ["ab", "a[a-z]", "[a-z]+"].map(|p| Regex::parse(p).unwrap());
+        let dfa = Nfa::from_rules(&rules).to_dfa();
+        let run = |s: &str| dfa.longest_match(dfa.start(), s, |c| dfa.class_of(c));
+        assert_eq!(run("ab"), Some((0, 2)));
+        assert_eq!(run("ac"), Some((1, 2)));
+        assert_eq!(run("abc"), Some((2, 3)));
+        assert_eq!(run("b"), Some((2, 1)));
+    }
+
+    #[test]
+    fn empty_rules_accept_at_start() {
+        let dfa = Nfa::from_rules(&[Regex::parse("a*").unwrap()]).to_dfa();
+        assert_eq!(dfa.accept(dfa.start()), Some(0));
+    }
+}
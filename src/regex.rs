@@ -0,0 +1,480 @@
+//! Token regexes, and compiling them down to a [`Dfa`].
+//!
+//! The syntax is the usual flex-like subset: literals, `.`, `[...]` and
+//! `[^...]` classes with ranges, `\d` `\w` `\s` and their negations, `"..."`
+//! quoted strings, grouping, `|`, and the `*` `+` `?` `{n}` `{n,}` `{n,m}`
+//! quantifiers. Patterns are ASCII; outside a class or `.`, non-ASCII
+//! characters all look the same to the automaton, so they can only be
+//! matched as a group (by `.` or a negated class).
+//!
+//! ```
+//! use cpsc323_lexer::regex;
+//!
+//! let dfa = regex::compile(&["while", "[A-Za-z_][A-Za-z0-9_]*"]).unwrap();
+//! let m = dfa.longest_match(dfa.start(), "while(", |c| dfa.class_of(c));
+//! // Both rules match; the one listed first wins
+//! assert_eq!(m, Some((0, 5)));
+//! ```
+
+use core::fmt;
+
+use crate::dfa::Dfa;
+use crate::nfa::Nfa;
+
+/// A set of characters: a bit per ASCII character, plus one bit standing
+/// for every non-ASCII character at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct CharSet {
+    ascii: u128,
+    non_ascii: bool,
+}
+
+/// Number of distinct symbols a [`CharSet`] can tell apart: the ASCII
+/// characters, then "anything else".
+pub const SYMBOLS: usize = 129;
+
+/// The symbol standing for every non-ASCII character.
+pub const NON_ASCII: usize = 128;
+
+impl CharSet {
+    pub fn empty() -> Self {
+        CharSet::default()
+    }
+
+    pub fn any() -> Self {
+        CharSet {
+            ascii: u128::MAX,
+            non_ascii: true,
+        }
+    }
+
+    /// The ASCII characters from `lo` to `hi` inclusive.
+    pub fn range(lo: u8, hi: u8) -> Self {
+        let mut s = CharSet::empty();
+        for c in lo..=hi.min(127) {
+            s.ascii |= 1 << c;
+        }
+        s
+    }
+
+    pub fn single(c: u8) -> Self {
+        CharSet::range(c, c)
+    }
+
+    pub fn union(self, other: CharSet) -> Self {
+        CharSet {
+            ascii: self.ascii | other.ascii,
+            non_ascii: self.non_ascii || other.non_ascii,
+        }
+    }
+
+    pub fn negate(self) -> Self {
+        CharSet {
+            ascii: !self.ascii,
+            non_ascii: !self.non_ascii,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ascii == 0 && !self.non_ascii
+    }
+
+    /// Whether the set contains symbol `sym`; see [`SYMBOLS`].
+    pub fn contains_symbol(&self, sym: usize) -> bool {
+        match sym {
+            NON_ASCII => self.non_ascii,
+            _ => self.ascii & (1 << sym) != 0,
+        }
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        match c.is_ascii() {
+            true => self.contains_symbol(c as usize),
+            false => self.non_ascii,
+        }
+    }
+}
+
+/// A parsed regex.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Regex {
+    /// Matches only the empty string
+    Empty,
+    Set(CharSet),
+    Concat(Vec<Regex>),
+    Alt(Vec<Regex>),
+    Star(Box<Regex>),
+    Plus(Box<Regex>),
+    Opt(Box<Regex>),
+    /// At least `min` and at most `max` (unbounded if `None`) repetitions
+    Repeat(Box<Regex>, u32, Option<u32>),
+}
+
+/// Why a regex didn't parse. Positions are byte offsets into the pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RegexError {
+    UnexpectedEnd,
+    Unexpected(char, usize),
+    UnclosedGroup(usize),
+    UnclosedClass(usize),
+    UnclosedString(usize),
+    BadEscape(usize),
+    BadRange(usize),
+    BadRepeat(usize),
+    NonAscii(usize),
+}
+
+impl fmt::Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegexError::UnexpectedEnd => write!(f, "unexpected end of pattern"),
+            RegexError::Unexpected(c, at) => write!(f, "unexpected {:?} at {}", c, at),
+            RegexError::UnclosedGroup(at) => write!(f, "unclosed group at {}", at),
+            RegexError::UnclosedClass(at) => write!(f, "unclosed character class at {}", at),
+            RegexError::UnclosedString(at) => write!(f, "unclosed string at {}", at),
+            RegexError::BadEscape(at) => write!(f, "bad escape at {}", at),
+            RegexError::BadRange(at) => write!(f, "bad range at {}", at),
+            RegexError::BadRepeat(at) => write!(f, "bad repetition at {}", at),
+            RegexError::NonAscii(at) => write!(f, "non-ASCII character at {}", at),
+        }
+    }
+}
+
+impl std::error::Error for RegexError {}
+
+// Upper bound on `{n,m}` counts, which get expanded into copies
+const MAX_REPEAT: u32 = 1000;
+
+impl Regex {
+    pub fn parse(pattern: &str) -> Result<Regex, RegexError> {
+        let mut p = Parser {
+            src: pattern,
+            pos: 0,
+        };
+        let re = p.alt()?;
+        match p.peek() {
+            None => Ok(re),
+            Some(c) => Err(RegexError::Unexpected(c, p.pos)),
+        }
+    }
+
+    /// The regex matching exactly `s`.
+    pub fn literal(s: &str) -> Result<Regex, RegexError> {
+        let mut parts = Vec::new();
+        for (i, c) in s.char_indices() {
+            if !c.is_ascii() {
+                return Err(RegexError::NonAscii(i));
+            }
+            parts.push(Regex::Set(CharSet::single(c as u8)));
+        }
+        Ok(Regex::Concat(parts))
+    }
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Result<char, RegexError> {
+        let c = self.peek().ok_or(RegexError::UnexpectedEnd)?;
+        if !c.is_ascii() {
+            return Err(RegexError::NonAscii(self.pos));
+        }
+        self.pos += 1;
+        Ok(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn alt(&mut self) -> Result<Regex, RegexError> {
+        let mut alts = vec![self.concat()?];
+        while self.eat('|') {
+            alts.push(self.concat()?);
+        }
+        Ok(match alts.len() {
+            1 => alts.pop().unwrap_or(Regex::Empty),
+            _ => Regex::Alt(alts),
+        })
+    }
+
+    fn concat(&mut self) -> Result<Regex, RegexError> {
+        let mut parts = Vec::new();
+        while !matches!(self.peek(), None | Some('|' | ')')) {
+            parts.push(self.repeat()?);
+        }
+        Ok(match parts.len() {
+            0 => Regex::Empty,
+            1 => parts.pop().unwrap_or(Regex::Empty),
+            _ => Regex::Concat(parts),
+        })
+    }
+
+    fn repeat(&mut self) -> Result<Regex, RegexError> {
+        let mut re = self.atom()?;
+        loop {
+            let at = self.pos;
+            re = match self.peek() {
+                Some('*') => Regex::Star(Box::new(re)),
+                Some('+') => Regex::Plus(Box::new(re)),
+                Some('?') => Regex::Opt(Box::new(re)),
+                Some('{') => {
+                    self.pos += 1;
+                    let min = self.number().ok_or(RegexError::BadRepeat(at))?;
+                    let max = match self.eat(',') {
+                        true if self.peek() == Some('}') => None,
+                        true => Some(self.number().ok_or(RegexError::BadRepeat(at))?),
+                        false => Some(min),
+                    };
+                    if !self.eat('}') || max.is_some_and(|m| m < min) {
+                        return Err(RegexError::BadRepeat(at));
+                    }
+                    if min.max(max.unwrap_or(0)) > MAX_REPEAT {
+                        return Err(RegexError::BadRepeat(at));
+                    }
+                    re = Regex::Repeat(Box::new(re), min, max);
+                    continue;
+                }
+                _ => return Ok(re),
+            };
+            self.pos += 1;
+        }
+    }
+
+    fn number(&mut self) -> Option<u32> {
+        let digits = self.src[self.pos..]
+            .bytes()
+            .take_while(u8::is_ascii_digit)
+            .count();
+        let n = self.src[self.pos..self.pos + digits].parse().ok()?;
+        self.pos += digits;
+        Some(n)
+    }
+
+    fn atom(&mut self) -> Result<Regex, RegexError> {
+        let at = self.pos;
+        match self.bump()? {
+            '(' => {
+                let re = self.alt()?;
+                if !self.eat(')') {
+                    return Err(RegexError::UnclosedGroup(at));
+                }
+                Ok(re)
+            }
+            '[' => self.class(at).map(Regex::Set),
+            '"' => {
+                let mut parts = Vec::new();
+                loop {
+                    match self.peek() {
+                        None => return Err(RegexError::UnclosedString(at)),
+                        Some('"') => break,
+                        Some('\\') => {
+                            self.pos += 1;
+                            parts.push(Regex::Set(self.escape()?));
+                        }
+                        Some(_) => parts.push(Regex::Set(CharSet::single(self.bump()? as u8))),
+                    }
+                }
+                self.pos += 1;
+                Ok(Regex::Concat(parts))
+            }
+            '.' => Ok(Regex::Set(CharSet::single(b'\n').negate())),
+            '\\' => self.escape().map(Regex::Set),
+            c @ ('*' | '+' | '?' | '{' | '}' | ')' | ']') => Err(RegexError::Unexpected(c, at)),
+            c => Ok(Regex::Set(CharSet::single(c as u8))),
+        }
+    }
+
+    // After a backslash
+    fn escape(&mut self) -> Result<CharSet, RegexError> {
+        let at = self.pos;
+        Ok(match self.bump()? {
+            'n' => CharSet::single(b'\n'),
+            't' => CharSet::single(b'\t'),
+            'r' => CharSet::single(b'\r'),
+            'd' => digit(),
+            'D' => digit().negate(),
+            'w' => word(),
+            'W' => word().negate(),
+            's' => space(),
+            'S' => space().negate(),
+            c if c.is_ascii_punctuation() || c == ' ' => CharSet::single(c as u8),
+            _ => return Err(RegexError::BadEscape(at)),
+        })
+    }
+
+    // After the opening `[`
+    fn class(&mut self, at: usize) -> Result<CharSet, RegexError> {
+        let negated = self.eat('^');
+        let mut set = CharSet::empty();
+        let mut first = true;
+        loop {
+            let item_at = self.pos;
+            let lo = match self.peek() {
+                None => return Err(RegexError::UnclosedClass(at)),
+                Some(']') if !first => break,
+                Some('\\') => {
+                    self.pos += 1;
+                    let esc = self.escape()?;
+                    // Only single characters can start a range
+                    match single(esc) {
+                        Some(c) => c,
+                        None => {
+                            set = set.union(esc);
+                            first = false;
+                            continue;
+                        }
+                    }
+                }
+                Some(_) => self.bump()? as u8,
+            };
+            first = false;
+            // A `-` right before the `]` is literal
+            let hi = if self.peek() == Some('-') && !self.src[self.pos + 1..].starts_with(']') {
+                self.pos += 1;
+                let hi = match self.bump() {
+                    Ok('\\') => single(self.escape()?).ok_or(RegexError::BadRange(item_at))?,
+                    Ok(c) => c as u8,
+                    Err(RegexError::UnexpectedEnd) => return Err(RegexError::UnclosedClass(at)),
+                    Err(e) => return Err(e),
+                };
+                if hi < lo {
+                    return Err(RegexError::BadRange(item_at));
+                }
+                hi
+            } else {
+                lo
+            };
+            set = set.union(CharSet::range(lo, hi));
+        }
+        self.pos += 1;
+        Ok(if negated { set.negate() } else { set })
+    }
+}
+
+fn digit() -> CharSet {
+    CharSet::range(b'0', b'9')
+}
+
+fn word() -> CharSet {
+    CharSet::range(b'A', b'Z')
+        .union(CharSet::range(b'a', b'z'))
+        .union(digit())
+        .union(CharSet::single(b'_'))
+}
+
+fn space() -> CharSet {
+    [b' ', b'\t', b'\r', b'\n', 0x0b, 0x0c]
+        .into_iter()
+        .fold(CharSet::empty(), |s, c| s.union(CharSet::single(c)))
+}
+
+// The character in a one-character set
+fn single(set: CharSet) -> Option<u8> {
+    match (set.ascii.count_ones(), set.non_ascii) {
+        (1, false) => Some(set.ascii.trailing_zeros() as u8),
+        _ => None,
+    }
+}
+
+/// Compile token regexes into one minimal DFA whose accepting states say
+/// which pattern matched. Where several patterns match the same text, the
+/// earliest one wins.
+pub fn compile(patterns: &[&str]) -> Result<Dfa<usize>, RegexError> {
+    let res = patterns
+        .iter()
+        .map(|p| Regex::parse(p))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Nfa::from_rules(&res).to_dfa().minimize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Length of the longest match of `dfa` at the start of `s`, and the rule
+    fn munch(dfa: &Dfa<usize>, s: &str) -> Option<(usize, usize)> {
+        dfa.longest_match(dfa.start(), s, |c| dfa.class_of(c))
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(Regex::parse("[z-a]"), Err(RegexError::BadRange(1)));
+        assert_eq!(Regex::parse("[a-\\d]"), Err(RegexError::BadRange(1)));
+        assert_eq!(Regex::parse("a{3,2}"), Err(RegexError::BadRepeat(1)));
+        assert_eq!(Regex::parse("a{x}"), Err(RegexError::BadRepeat(1)));
+        assert_eq!(Regex::parse("a{2"), Err(RegexError::BadRepeat(1)));
+        assert_eq!(Regex::parse("a{1001}"), Err(RegexError::BadRepeat(1)));
+        assert_eq!(Regex::parse("(ab"), Err(RegexError::UnclosedGroup(0)));
+        assert_eq!(Regex::parse("x[abc"), Err(RegexError::UnclosedClass(1)));
+        assert_eq!(Regex::parse("[a-"), Err(RegexError::UnclosedClass(0)));
+        assert_eq!(Regex::parse("\"abc"), Err(RegexError::UnclosedString(0)));
+        assert_eq!(Regex::parse("\\q"), Err(RegexError::BadEscape(1)));
+        assert_eq!(Regex::parse("a)"), Err(RegexError::Unexpected(')', 1)));
+        assert_eq!(Regex::parse("*a"), Err(RegexError::Unexpected('*', 0)));
+        assert_eq!(Regex::parse("é"), Err(RegexError::NonAscii(0)));
+        assert_eq!(Regex::parse("a\\"), Err(RegexError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn classes() {
+        let re = Regex::parse("[^a-c\\d-]").unwrap();
+        let Regex::Set(set) = re else { panic!("not a set: {:?}", re) };
+        assert!(!set.contains('b') && !set.contains('7') && !set.contains('-'));
+        assert!(set.contains('d') && set.contains('é'));
+        // `.` is anything but a newline, non-ASCII included
+        let Regex::Set(dot) = Regex::parse(".").unwrap() else { panic!() };
+        assert!(dot.contains('é') && !dot.contains('\n'));
+    }
+
+    #[test]
+    fn bounded_repeat() {
+        assert_eq!(
+            Regex::parse("a{2,3}"),
+            Ok(Regex::Repeat(Box::new(Regex::Set(CharSet::single(b'a'))), 2, Some(3)))
+        );
+        let dfa = compile(&["a{2,3}"]).unwrap();
+        assert_eq!(munch(&dfa, "a"), None);
+        assert_eq!(munch(&dfa, "aa"), Some((0, 2)));
+        assert_eq!(munch(&dfa, "aaaa"), Some((0, 3)));
+
+        let exact = compile(&["a{2}"]).unwrap();
+        assert_eq!(munch(&exact, "aaa"), Some((0, 2)));
+        let open = compile(&["a{2,}"]).unwrap();
+        assert_eq!(munch(&open, "a"), None);
+        assert_eq!(munch(&open, "aaaaa"), Some((0, 5)));
+        let zero = compile(&["ba{0,1}"]).unwrap();
+        assert_eq!(munch(&zero, "baa"), Some((0, 2)));
+    }
+
+    #[test]
+    fn earlier_rules_win_ties() {
+        let dfa = compile(&["if", "[a-z]+", "[a-z]+[0-9]"]).unwrap();
+        assert_eq!(munch(&dfa, "if("), Some((0, 2)));
+        // Longest match beats priority
+        assert_eq!(munch(&dfa, "iffy"), Some((1, 4)));
+        assert_eq!(munch(&dfa, "if2"), Some((2, 3)));
+        let swapped = compile(&["[a-z]+", "if"]).unwrap();
+        assert_eq!(munch(&swapped, "if"), Some((0, 2)));
+    }
+
+    #[test]
+    fn literals_are_not_patterns() {
+        let re = Regex::literal("a*").unwrap();
+        let dfa = Nfa::from_rules(&[re]).to_dfa().minimize();
+        assert_eq!(munch(&dfa, "a*"), Some((0, 2)));
+        assert_eq!(munch(&dfa, "aa"), None);
+    }
+}
@@ -0,0 +1,96 @@
+// Runs of whitespace and identifier bytes, found eight bytes at a time
+// instead of stepping the automaton through them one by one. Each test
+// takes a word of input and sets the high bit of every byte in it that
+// passes; the bytes must match the built-in table's Space and identifier
+// classes, which the tests below check.
+
+const ONES: u64 = 0x0101_0101_0101_0101;
+const HIGH: u64 = 0x8080_8080_8080_8080;
+
+// Bytes equal to `b`
+fn eq(w: u64, b: u8) -> u64 {
+    let x = w ^ (ONES * b as u64);
+    // The low seven bits of a byte added to 0x7f carry into its high bit
+    // unless they're all zero, and this can't carry into the next byte
+    !(((x & !HIGH) + !HIGH) | x) & HIGH
+}
+
+// ASCII bytes from `lo` to `hi`
+fn between(w: u64, lo: u8, hi: u8) -> u64 {
+    let low = w & !HIGH;
+    let at_least_lo = low + ONES * (0x80 - lo) as u64;
+    let above_hi = low + ONES * (0x7f - hi) as u64;
+    at_least_lo & !above_hi & !w & HIGH
+}
+
+fn blank(w: u64) -> u64 {
+    eq(w, b' ') | eq(w, b'\t') | eq(w, b'\r') | eq(w, b'\n')
+}
+
+fn ident(w: u64) -> u64 {
+    // Setting 0x20 folds upper case letters onto lower case ones
+    between(w | (ONES * 0x20), b'a', b'z') | between(w, b'0', b'9') | eq(w, b'_')
+}
+
+// Length of the run of bytes at the start of `bytes` that pass `test`
+fn run(bytes: &[u8], test: fn(u64) -> u64) -> usize {
+    let mut chunks = bytes.chunks_exact(8);
+    let mut len = 0;
+    for chunk in chunks.by_ref() {
+        let failed = !test(u64::from_le_bytes(chunk.try_into().unwrap())) & HIGH;
+        if failed != 0 {
+            return len + failed.trailing_zeros() as usize / 8;
+        }
+        len += 8;
+    }
+    // Pad the tail with zero bytes, which fail every test
+    let mut tail = [0; 8];
+    tail[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+    len + (!test(u64::from_le_bytes(tail)) & HIGH).trailing_zeros() as usize / 8
+}
+
+// Length of the run of spaces, tabs and line breaks `bytes` starts with
+pub(crate) fn blanks(bytes: &[u8]) -> usize {
+    run(bytes, blank)
+}
+
+// Length of the run of ASCII letters, digits and underscores `bytes`
+// starts with
+pub(crate) fn ident_chars(bytes: &[u8]) -> usize {
+    run(bytes, ident)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dfa::StateId;
+    use crate::table::{self, States};
+
+    #[test]
+    fn agrees_with_the_table() {
+        let dfa = table::builtin();
+        let stays = |state: States, b: u8| {
+            dfa.next(state as StateId, dfa.class_of(b as char)) == state as StateId
+        };
+        for b in 0..=u8::MAX {
+            let ascii = b.is_ascii();
+            assert_eq!(blanks(&[b]) == 1, ascii && stays(States::Blank, b), "{:?}", b as char);
+            assert_eq!(
+                ident_chars(&[b]) == 1,
+                ascii && stays(States::DefiningIdentifier, b),
+                "{:?}",
+                b as char
+            );
+        }
+    }
+
+    #[test]
+    fn runs_across_words() {
+        assert_eq!(blanks(b""), 0);
+        assert_eq!(blanks(b"  \t\r\n  \n   x"), 11);
+        assert_eq!(blanks(b"           "), 11);
+        assert_eq!(ident_chars(b"first_long_identifier_name = 1"), 26);
+        assert_eq!(ident_chars(b"Zz09_@"), 5);
+        assert_eq!(ident_chars("größe".as_bytes()), 2);
+    }
+}
@@ -0,0 +1,44 @@
+use crate::cursor::Cursor;
+use crate::dfa::Dfa;
+use crate::{LexerError, Span};
+
+// The token loop of a lexer that's nothing but a DFA, like a spec's or a
+// derived one: longest match after longest match, until `act` makes a
+// token (or error) of one; it returns `None` for text to skip. A character
+// no match starts is an IllegalCharacter error, and lexing carries on
+// after it.
+pub(crate) fn next<'src, A: Copy, T>(
+    chas: &mut Cursor<'src>,
+    dfa: &Dfa<A>,
+    mut act: impl FnMut(A, &'src str, Span) -> Option<Result<T, LexerError>>,
+) -> Option<Result<T, LexerError>> {
+    loop {
+        let start = chas.here();
+        let c = chas.peek()?;
+        let (acc, len) = match dfa.longest_match_bytes(dfa.start(), chas.rest(), |c| dfa.class_of(c)) {
+            Some(m) => m,
+            None => {
+                chas.next();
+                return Some(Err(LexerError::IllegalCharacter(c, chas.to(start))));
+            }
+        };
+        chas.advance(len);
+        let span = chas.to(start);
+        if let Some(res) = act(acc, chas.slice(span), span) {
+            return Some(res);
+        }
+    }
+}
+
+// Split what a lexer yields into its tokens and its errors
+pub(crate) fn tokenize<T>(lexer: impl Iterator<Item = Result<T, LexerError>>) -> (Vec<T>, Vec<LexerError>) {
+    let mut toks = Vec::new();
+    let mut errors = Vec::new();
+    for res in lexer {
+        match res {
+            Ok(tok) => toks.push(tok),
+            Err(e) => errors.push(e),
+        }
+    }
+    (toks, errors)
+}
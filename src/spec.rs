@@ -0,0 +1,380 @@
+//! Lexers described by a flex-style spec file instead of code.
+//!
+//! A spec has two sections split by a `%%` line. The first names regexes
+//! for reuse; the second lists rules, each a [`regex`](crate::regex)
+//! pattern followed by what to do with text it matches:
+//!
+//! ```text
+//! # Lines starting with `#` are comments in either section
+//! digit   [0-9]
+//! letter  [A-Za-z_]
+//! %%
+//! while|if                          emit keyword
+//! {letter}({letter}|{digit})*       emit identifier
+//! {digit}+                          emit number
+//! [ \t\r\n]+                        skip
+//! .                                 error illegal character
+//! ```
+//!
+//! `{name}` in a pattern stands for the definition of that name. A pattern
+//! ends at the first whitespace outside a `[...]` class or `"..."` string.
+//! Actions are `emit KIND`, yielding a token of that kind; `skip`; and
+//! `error MESSAGE`, yielding [`LexerError::Rejected`] (the message is
+//! optional). As in flex, the longest match wins, and between rules
+//! matching the same text, the one listed first.
+//!
+//! ```
+//! use cpsc323_lexer::spec::Spec;
+//!
+//! let spec = Spec::parse("%%\n[0-9]+ emit num\n\" \" skip\n").unwrap();
+//! let kinds: Vec<_> = spec.lexer("1 23").map(|t| t.unwrap().kind).collect();
+//! assert_eq!(kinds, ["num", "num"]);
+//! ```
+
+use core::fmt;
+use std::collections::HashMap;
+
+use crate::analysis::{self, Report};
+use crate::cursor::Cursor;
+use crate::dfa::Dfa;
+use crate::nfa::Nfa;
+use crate::regex::{Regex, RegexError};
+use crate::scan;
+use crate::{LexerError, Span};
+
+/// What to do with the text a rule matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Yield a token of this kind
+    Emit(String),
+    Skip,
+    /// Yield [`LexerError::Rejected`] with this message
+    Error(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    /// The pattern as written, before definitions are substituted
+    pub pattern: String,
+    pub action: Action,
+    /// Line of the spec the rule is on
+    pub line: usize,
+}
+
+/// Why a spec didn't load. Line numbers are 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SpecError {
+    /// No `%%` line, so no rules section
+    MissingSeparator,
+    /// A second `%%`; flex's user code section isn't supported
+    ExtraSeparator(usize),
+    BadDefinition(usize),
+    UndefinedName(String, usize),
+    BadPattern(RegexError, usize),
+    MissingAction(usize),
+    BadAction(String, usize),
+    /// A rule that matches the empty string, which would never advance
+    EmptyMatch(usize),
+    NoRules,
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpecError::MissingSeparator => write!(f, "no `%%` line before the rules"),
+            SpecError::ExtraSeparator(line) => write!(f, "line {}: unexpected second `%%`", line),
+            SpecError::BadDefinition(line) => write!(f, "line {}: expected `NAME PATTERN`", line),
+            SpecError::UndefinedName(name, line) => {
+                write!(f, "line {}: `{{{}}}` isn't defined", line, name)
+            }
+            SpecError::BadPattern(e, line) => write!(f, "line {}: {}", line, e),
+            SpecError::MissingAction(line) => write!(f, "line {}: rule has no action", line),
+            SpecError::BadAction(action, line) => {
+                write!(f, "line {}: unknown action `{}`", line, action)
+            }
+            SpecError::EmptyMatch(line) => {
+                write!(f, "line {}: pattern matches the empty string", line)
+            }
+            SpecError::NoRules => write!(f, "no rules"),
+        }
+    }
+}
+
+impl std::error::Error for SpecError {}
+
+/// A loaded spec, compiled down to one DFA over all its rules.
+#[derive(Debug, Clone)]
+pub struct Spec {
+    rules: Vec<Rule>,
+    regexes: Vec<Regex>,
+    dfa: Dfa<usize>,
+}
+
+impl Spec {
+    pub fn parse(src: &str) -> Result<Spec, SpecError> {
+        let mut defs: HashMap<String, String> = HashMap::new();
+        let mut rules = Vec::new();
+        let mut regexes = Vec::new();
+        let mut in_rules = false;
+        for (i, line) in src.lines().enumerate() {
+            let n = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line == "%%" {
+                if in_rules {
+                    return Err(SpecError::ExtraSeparator(n));
+                }
+                in_rules = true;
+                continue;
+            }
+            let (pattern, rest) = split_pattern(line);
+            if !in_rules {
+                let valid = pattern.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                    && pattern.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+                if !valid || rest.is_empty() {
+                    return Err(SpecError::BadDefinition(n));
+                }
+                let def = expand(rest, &defs, n)?;
+                // Check it now, so errors point at the definition
+                Regex::parse(&def).map_err(|e| SpecError::BadPattern(e, n))?;
+                defs.insert(pattern.to_string(), def);
+                continue;
+            }
+            let expanded = expand(pattern, &defs, n)?;
+            regexes.push(Regex::parse(&expanded).map_err(|e| SpecError::BadPattern(e, n))?);
+            rules.push(Rule {
+                pattern: pattern.to_string(),
+                action: action(rest, n)?,
+                line: n,
+            });
+        }
+        if !in_rules {
+            return Err(SpecError::MissingSeparator);
+        }
+        if rules.is_empty() {
+            return Err(SpecError::NoRules);
+        }
+        let dfa = Nfa::from_rules(&regexes).to_dfa().minimize();
+        if let Some(r) = dfa.accept(dfa.start()) {
+            return Err(SpecError::EmptyMatch(rules[r].line));
+        }
+        Ok(Spec { rules, regexes, dfa })
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Look for rules that overlap or can never match; see
+    /// [`analysis`](crate::analysis). Rule numbers index [`Spec::rules`].
+    pub fn check(&self) -> Report {
+        analysis::analyze(&self.regexes)
+    }
+
+    /// The compiled rules; accepting states say which rule matched.
+    pub fn dfa(&self) -> &Dfa<usize> {
+        &self.dfa
+    }
+
+    pub fn lexer<'s, 'src>(&'s self, src: &'src str) -> SpecLexer<'s, 'src> {
+        SpecLexer {
+            spec: self,
+            chas: Cursor::new(src),
+        }
+    }
+}
+
+// Split a line into its pattern and whatever follows, trimmed. The pattern
+// ends at whitespace outside a class or string.
+fn split_pattern(line: &str) -> (&str, &str) {
+    let mut class = false;
+    let mut quoted = false;
+    let mut chars = line.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '"' if !class => quoted = !quoted,
+            '[' if !quoted => class = true,
+            ']' if !quoted => class = false,
+            c if c.is_whitespace() && !class && !quoted => {
+                return (&line[..i], line[i..].trim());
+            }
+            _ => {}
+        }
+    }
+    (line, "")
+}
+
+// Replace each `{name}` outside a class or string with its definition
+fn expand(pattern: &str, defs: &HashMap<String, String>, line: usize) -> Result<String, SpecError> {
+    let mut out = String::new();
+    let mut class = false;
+    let mut quoted = false;
+    let mut chars = pattern.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                out.push(c);
+                if let Some((_, c)) = chars.next() {
+                    out.push(c);
+                }
+                continue;
+            }
+            '"' if !class => quoted = !quoted,
+            '[' if !quoted => class = true,
+            ']' if !quoted => class = false,
+            // `{` then a letter is a name; `{` then a digit is a repeat count
+            '{' if !class
+                && !quoted
+                && chars.peek().is_some_and(|&(_, c)| c.is_ascii_alphabetic() || c == '_') =>
+            {
+                let name: String = pattern[i + 1..].chars().take_while(|&c| c != '}').collect();
+                let def = defs
+                    .get(&name)
+                    .ok_or_else(|| SpecError::UndefinedName(name.clone(), line))?;
+                out.push('(');
+                out.push_str(def);
+                out.push(')');
+                chars.nth(name.chars().count());
+                continue;
+            }
+            _ => {}
+        }
+        out.push(c);
+    }
+    Ok(out)
+}
+
+fn action(text: &str, line: usize) -> Result<Action, SpecError> {
+    let (word, arg) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let arg = arg.trim();
+    match word {
+        "" => Err(SpecError::MissingAction(line)),
+        "skip" if arg.is_empty() => Ok(Action::Skip),
+        "emit" if !arg.is_empty() && !arg.contains(char::is_whitespace) => {
+            Ok(Action::Emit(arg.to_string()))
+        }
+        "error" if arg.is_empty() => Ok(Action::Error(String::from("Rejected"))),
+        "error" => Ok(Action::Error(arg.to_string())),
+        _ => Err(SpecError::BadAction(text.to_string(), line)),
+    }
+}
+
+/// A token produced by a [`Spec`]'s lexer. `kind` is the name its rule
+/// emits; `lex` borrows from the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpecToken<'s, 'src> {
+    pub kind: &'s str,
+    pub lex: &'src str,
+    pub span: Span,
+}
+
+/// Iterator over the tokens a [`Spec`] finds in a source string.
+///
+/// Errors don't stop it: text an `error` rule matches, or a character no
+/// rule matches, is yielded as an `Err` and lexing carries on after it.
+pub struct SpecLexer<'s, 'src> {
+    spec: &'s Spec,
+    chas: Cursor<'src>,
+}
+
+impl<'s, 'src> SpecLexer<'s, 'src> {
+    /// Lex the whole input; like [`Lexer::tokenize`](crate::Lexer::tokenize),
+    /// this keeps going after errors and hands them all back at the end.
+    pub fn tokenize(self) -> (Vec<SpecToken<'s, 'src>>, Vec<LexerError>) {
+        scan::tokenize(self)
+    }
+}
+
+impl<'s, 'src> Iterator for SpecLexer<'s, 'src> {
+    type Item = Result<SpecToken<'s, 'src>, LexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rules = &self.spec.rules;
+        scan::next(&mut self.chas, &self.spec.dfa, |rule, lex, span| match &rules[rule].action {
+            Action::Skip => None,
+            Action::Emit(kind) => Some(Ok(SpecToken { kind, lex, span })),
+            Action::Error(msg) => Some(Err(LexerError::Rejected(msg.clone(), span))),
+        })
+    }
+}
+
+impl std::iter::FusedIterator for SpecLexer<'_, '_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defs(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|&(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn patterns_end_at_whitespace_outside_classes_and_strings() {
+        assert_eq!(split_pattern("[0-9]+   emit num"), ("[0-9]+", "emit num"));
+        assert_eq!(split_pattern("[ \t]+ skip"), ("[ \t]+", "skip"));
+        assert_eq!(split_pattern("\"a b\" emit ab"), ("\"a b\"", "emit ab"));
+        assert_eq!(split_pattern("\"[\" emit lb"), ("\"[\"", "emit lb"));
+        assert_eq!(split_pattern(r"\  skip"), (r"\ ", "skip"));
+        assert_eq!(split_pattern("abc"), ("abc", ""));
+    }
+
+    #[test]
+    fn names_expand_but_repeat_counts_dont() {
+        let defs = defs(&[("letter", "[A-Za-z]"), ("digit", "[0-9]")]);
+        assert_eq!(
+            expand("{letter}({letter}|{digit})*", &defs, 1),
+            Ok(String::from("([A-Za-z])(([A-Za-z])|([0-9]))*"))
+        );
+        assert_eq!(expand("{digit}{2,3}", &defs, 1), Ok(String::from("([0-9]){2,3}")));
+        // Left alone inside classes and strings
+        assert_eq!(expand("[{digit}]\"{digit}\"", &defs, 1), Ok(String::from("[{digit}]\"{digit}\"")));
+        assert_eq!(expand(r"\{digit}", &defs, 1), Ok(String::from(r"\{digit}")));
+        assert_eq!(expand("a{nope}", &defs, 3), Err(SpecError::UndefinedName(String::from("nope"), 3)));
+    }
+
+    #[test]
+    fn actions() {
+        assert_eq!(action("skip", 1), Ok(Action::Skip));
+        assert_eq!(action("emit  num", 1), Ok(Action::Emit(String::from("num"))));
+        assert_eq!(action("error", 1), Ok(Action::Error(String::from("Rejected"))));
+        assert_eq!(action("error not allowed here", 1), Ok(Action::Error(String::from("not allowed here"))));
+        assert_eq!(action("", 2), Err(SpecError::MissingAction(2)));
+        for bad in ["emit", "emit two words", "skip it", "yield x"] {
+            assert_eq!(action(bad, 2), Err(SpecError::BadAction(bad.to_string(), 2)));
+        }
+    }
+
+    #[test]
+    fn bad_specs() {
+        assert_eq!(Spec::parse("a emit a").unwrap_err(), SpecError::MissingSeparator);
+        assert_eq!(Spec::parse("%%\na emit a\n%%").unwrap_err(), SpecError::ExtraSeparator(3));
+        assert_eq!(Spec::parse("9x [0-9]\n%%\na skip").unwrap_err(), SpecError::BadDefinition(1));
+        assert_eq!(Spec::parse("x\n%%\na skip").unwrap_err(), SpecError::BadDefinition(1));
+        assert_eq!(Spec::parse("%%\n# nothing\n").unwrap_err(), SpecError::NoRules);
+        assert_eq!(Spec::parse("%%\na emit a\nb* skip").unwrap_err(), SpecError::EmptyMatch(3));
+        assert!(matches!(Spec::parse("%%\na( emit a"), Err(SpecError::BadPattern(_, 2))));
+        // Definitions are checked where they're written
+        assert!(matches!(Spec::parse("d [0-\n%%\n{d} skip"), Err(SpecError::BadPattern(_, 1))));
+    }
+
+    #[test]
+    fn lexing_with_counts_and_errors() {
+        let spec = Spec::parse("x [a]\na {x}{2,3}\n%%\n{a} emit as\n\" \" skip\nb error").unwrap();
+        let toks: Vec<_> = spec.lexer("aaaaa b").collect();
+        let span = |start, end| Span { start, end, line: 1, col: start + 1 };
+        assert_eq!(
+            toks,
+            [
+                Ok(SpecToken { kind: "as", lex: "aaa", span: span(0, 3) }),
+                Ok(SpecToken { kind: "as", lex: "aa", span: span(3, 5) }),
+                Err(LexerError::Rejected(String::from("Rejected"), span(6, 7))),
+            ]
+        );
+    }
+}
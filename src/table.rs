@@ -0,0 +1,703 @@
+//! The lexer's finite automaton, as data.
+//!
+//! Each token is a path through [`States`]: the [`TRANSITIONS`] say where
+//! each [`Class`] of character leads, and [`ACCEPTS`] says what a token
+//! ending in a given state is. Adding a token means adding a state, its
+//! transitions and its accept entry here; the engine in the lexer doesn't
+//! change. Strings and comments are delimited rather than regular (escapes,
+//! configurable and nestable delimiters), so the lexer scans those itself
+//! before consulting the automaton.
+
+use std::sync::OnceLock;
+
+use crate::dfa::{Dfa, StateId};
+use crate::dot::Dot;
+use crate::{Operator, Radix, Separator, TokenType};
+
+/// Character classes. Characters in the same class are interchangeable as
+/// far as the automaton is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Class {
+    /// Anything no token can contain
+    Other,
+    /// Space, tab, carriage return and newline
+    Space,
+    Zero,
+    One,
+    /// `2` to `7`
+    Octal,
+    /// `8` and `9`
+    Decimal,
+    Underscore,
+    /// `b`, the binary prefix (and a hex digit)
+    LowerB,
+    /// `e` and `E`, which start exponents (and are hex digits)
+    LowerE,
+    /// `x`, the hex prefix
+    LowerX,
+    /// `o`, the octal prefix
+    LowerO,
+    /// The remaining hex digits, `[acdfABCDF]`
+    HexLetter,
+    /// The remaining letters (and, in Unicode mode, XID_Start characters)
+    Letter,
+    /// XID_Continue characters that can't start an identifier; Unicode mode
+    /// only
+    UnicodeContinue,
+    Period,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Less,
+    Greater,
+    Equals,
+    Bang,
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Comma,
+    Colon,
+    Semicolon,
+    Hash,
+    Dollar,
+}
+
+pub const NUM_CLASSES: usize = Class::Dollar as usize + 1;
+
+impl Class {
+    pub const ALL: [Class; NUM_CLASSES] = [
+        Class::Other,
+        Class::Space,
+        Class::Zero,
+        Class::One,
+        Class::Octal,
+        Class::Decimal,
+        Class::Underscore,
+        Class::LowerB,
+        Class::LowerE,
+        Class::LowerX,
+        Class::LowerO,
+        Class::HexLetter,
+        Class::Letter,
+        Class::UnicodeContinue,
+        Class::Period,
+        Class::Plus,
+        Class::Minus,
+        Class::Star,
+        Class::Slash,
+        Class::Less,
+        Class::Greater,
+        Class::Equals,
+        Class::Bang,
+        Class::LParen,
+        Class::RParen,
+        Class::LBrace,
+        Class::RBrace,
+        Class::LBracket,
+        Class::RBracket,
+        Class::Comma,
+        Class::Colon,
+        Class::Semicolon,
+        Class::Hash,
+        Class::Dollar,
+    ];
+
+    pub const fn of_ascii(c: u8) -> Class {
+        match c {
+            b' ' | b'\t' | b'\r' | b'\n' => Class::Space,
+            b'0' => Class::Zero,
+            b'1' => Class::One,
+            b'2'..=b'7' => Class::Octal,
+            b'8' | b'9' => Class::Decimal,
+            b'_' => Class::Underscore,
+            b'b' => Class::LowerB,
+            b'e' | b'E' => Class::LowerE,
+            b'x' => Class::LowerX,
+            b'o' => Class::LowerO,
+            b'a' | b'c' | b'd' | b'f' | b'A'..=b'D' | b'F' => Class::HexLetter,
+            b'a'..=b'z' | b'A'..=b'Z' => Class::Letter,
+            b'.' => Class::Period,
+            b'+' => Class::Plus,
+            b'-' => Class::Minus,
+            b'*' => Class::Star,
+            b'/' => Class::Slash,
+            b'<' => Class::Less,
+            b'>' => Class::Greater,
+            b'=' => Class::Equals,
+            b'!' => Class::Bang,
+            b'(' => Class::LParen,
+            b')' => Class::RParen,
+            b'{' => Class::LBrace,
+            b'}' => Class::RBrace,
+            b'[' => Class::LBracket,
+            b']' => Class::RBracket,
+            b',' => Class::Comma,
+            b':' => Class::Colon,
+            b';' => Class::Semicolon,
+            b'#' => Class::Hash,
+            b'$' => Class::Dollar,
+            _ => Class::Other,
+        }
+    }
+}
+
+/// States of the automaton. `Start`, `StartMember` and `StartLeadingDot`
+/// are start conditions: the lexer begins each token in whichever one fits
+/// the context (after something with members, or with leading-dot reals
+/// on). They share every transition except the one on `.`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum States {
+    Dead,
+    Start,
+    StartMember,
+    StartLeadingDot,
+    Blank,
+    DefiningIdentifier,
+    Zero,
+    DefiningInteger,
+    // `1.`: a real so far, or the start of `1..`
+    IntegerDot,
+    // `1..`, which gives back the `..`
+    IntegerRange,
+    DefiningReal,
+    // After the `e` of an exponent, which may be followed by a sign
+    DefiningExponent,
+    // After the exponent's sign; a digit must follow
+    DefiningExponentSign,
+    DefiningExponentDigits,
+    HexPrefix,
+    HexDigits,
+    // `0x1.` and `0x1..`, as `1.` and `1..` but never a real
+    HexDot,
+    HexRange,
+    OctalPrefix,
+    OctalDigits,
+    OctalDot,
+    OctalRange,
+    BinaryPrefix,
+    BinaryDigits,
+    BinaryDot,
+    BinaryRange,
+    // Dead ends that report errors
+    BadIdentifier,
+    BadDigit,
+    BadDot,
+    IllegalCharacter,
+    // A `.` that can't be member access
+    Dot,
+    MemberDot,
+    LeadingDot,
+    Range,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Assign,
+    EqEq,
+    FatArrow,
+    Bang,
+    NotEq,
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Comma,
+    Colon,
+    Semicolon,
+    Hash,
+    Dollar,
+    DoubleDollar,
+}
+
+pub const NUM_STATES: usize = States::DoubleDollar as usize + 1;
+
+impl States {
+    pub const ALL: [States; NUM_STATES] = [
+        States::Dead,
+        States::Start,
+        States::StartMember,
+        States::StartLeadingDot,
+        States::Blank,
+        States::DefiningIdentifier,
+        States::Zero,
+        States::DefiningInteger,
+        States::IntegerDot,
+        States::IntegerRange,
+        States::DefiningReal,
+        States::DefiningExponent,
+        States::DefiningExponentSign,
+        States::DefiningExponentDigits,
+        States::HexPrefix,
+        States::HexDigits,
+        States::HexDot,
+        States::HexRange,
+        States::OctalPrefix,
+        States::OctalDigits,
+        States::OctalDot,
+        States::OctalRange,
+        States::BinaryPrefix,
+        States::BinaryDigits,
+        States::BinaryDot,
+        States::BinaryRange,
+        States::BadIdentifier,
+        States::BadDigit,
+        States::BadDot,
+        States::IllegalCharacter,
+        States::Dot,
+        States::MemberDot,
+        States::LeadingDot,
+        States::Range,
+        States::Plus,
+        States::Minus,
+        States::Star,
+        States::Slash,
+        States::Lt,
+        States::Le,
+        States::Gt,
+        States::Ge,
+        States::Assign,
+        States::EqEq,
+        States::FatArrow,
+        States::Bang,
+        States::NotEq,
+        States::LParen,
+        States::RParen,
+        States::LBrace,
+        States::RBrace,
+        States::LBracket,
+        States::RBracket,
+        States::Comma,
+        States::Colon,
+        States::Semicolon,
+        States::Hash,
+        States::Dollar,
+        States::DoubleDollar,
+    ];
+}
+
+/// What a token ending in an accepting state is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Accept {
+    /// Skipped
+    Whitespace,
+    Identifier,
+    Integer(Radix),
+    Real,
+    /// An integer followed by `..`. The `..` is trailing context: it isn't
+    /// part of the token and is lexed again as a range.
+    IntegerBeforeRange(Radix),
+    Operator(Operator),
+    Separator(Separator),
+    // Errors; see the LexerError variants of the same names
+    IllegalDot,
+    InvalidIdentifier,
+    InvalidDigit,
+    MissingDigits,
+    MissingExponent,
+    IllegalCharacter,
+}
+
+impl Accept {
+    /// The type of token produced, or `None` for whitespace and errors.
+    /// Identifiers may still turn out to be keywords.
+    pub fn token_type(&self) -> Option<TokenType> {
+        Some(match self {
+            Accept::Identifier => TokenType::Identifier,
+            Accept::Integer(_) | Accept::IntegerBeforeRange(_) => TokenType::Number,
+            Accept::Real => TokenType::Real,
+            Accept::Operator(op) => TokenType::Operator(*op),
+            Accept::Separator(sep) => TokenType::Separator(*sep),
+            _ => return None,
+        })
+    }
+
+    /// Bytes of trailing context at the end of the match that don't belong
+    /// to the token.
+    pub fn trailing(&self) -> usize {
+        match self {
+            Accept::IntegerBeforeRange(_) => 2,
+            _ => 0,
+        }
+    }
+}
+
+use Class as C;
+use States as S;
+
+const DIGITS: &[Class] = &[C::Zero, C::One, C::Octal, C::Decimal];
+// Letters that can't continue a number (`e` can, as an exponent)
+const NON_E_LETTERS: &[Class] = &[C::Letter, C::LowerB, C::LowerX, C::LowerO, C::HexLetter];
+const IDENT_START: &[Class] = &[
+    C::Letter,
+    C::LowerB,
+    C::LowerE,
+    C::LowerX,
+    C::LowerO,
+    C::HexLetter,
+    C::Underscore,
+];
+const IDENT_CONTINUE: &[Class] = &[
+    C::Letter,
+    C::LowerB,
+    C::LowerE,
+    C::LowerX,
+    C::LowerO,
+    C::HexLetter,
+    C::Underscore,
+    C::Zero,
+    C::One,
+    C::Octal,
+    C::Decimal,
+    C::UnicodeContinue,
+];
+const HEX_DIGITS: &[Class] = &[
+    C::Zero,
+    C::One,
+    C::Octal,
+    C::Decimal,
+    C::LowerB,
+    C::LowerE,
+    C::HexLetter,
+];
+const NON_HEX_LETTERS: &[Class] = &[C::Letter, C::LowerX, C::LowerO];
+const ALL_LETTERS: &[Class] = &[
+    C::Letter,
+    C::LowerB,
+    C::LowerE,
+    C::LowerX,
+    C::LowerO,
+    C::HexLetter,
+];
+
+/// Transitions as `(from, on any of, to)`. Later entries override earlier
+/// ones. Entries from `Start` also apply to the other start conditions.
+pub const TRANSITIONS: &[(States, &[Class], States)] = &[
+    // Whitespace and anything that can't start a token
+    (S::Start, &[C::Space], S::Blank),
+    (S::Blank, &[C::Space], S::Blank),
+    (S::Start, &[C::Other, C::UnicodeContinue], S::IllegalCharacter),
+    // Identifiers: [A-Za-z_][A-Za-z0-9_]*
+    (S::Start, IDENT_START, S::DefiningIdentifier),
+    (S::DefiningIdentifier, IDENT_CONTINUE, S::DefiningIdentifier),
+    // Decimal integers and reals; a letter glued onto a number is an error
+    (S::Start, &[C::One, C::Octal, C::Decimal], S::DefiningInteger),
+    (S::Start, &[C::Zero], S::Zero),
+    (S::Zero, DIGITS, S::DefiningInteger),
+    (S::Zero, &[C::Underscore], S::DefiningInteger),
+    (S::Zero, NON_E_LETTERS, S::BadIdentifier),
+    (S::Zero, &[C::LowerX], S::HexPrefix),
+    (S::Zero, &[C::LowerO], S::OctalPrefix),
+    (S::Zero, &[C::LowerB], S::BinaryPrefix),
+    (S::Zero, &[C::LowerE], S::DefiningExponent),
+    (S::Zero, &[C::Period], S::IntegerDot),
+    (S::DefiningInteger, DIGITS, S::DefiningInteger),
+    (S::DefiningInteger, &[C::Underscore], S::DefiningInteger),
+    (S::DefiningInteger, NON_E_LETTERS, S::BadIdentifier),
+    (S::DefiningInteger, &[C::LowerE], S::DefiningExponent),
+    (S::DefiningInteger, &[C::Period], S::IntegerDot),
+    (S::IntegerDot, DIGITS, S::DefiningReal),
+    (S::IntegerDot, &[C::Underscore], S::DefiningReal),
+    (S::IntegerDot, NON_E_LETTERS, S::BadIdentifier),
+    (S::IntegerDot, &[C::LowerE], S::DefiningExponent),
+    (S::IntegerDot, &[C::Period], S::IntegerRange),
+    (S::DefiningReal, DIGITS, S::DefiningReal),
+    (S::DefiningReal, &[C::Underscore], S::DefiningReal),
+    (S::DefiningReal, NON_E_LETTERS, S::BadIdentifier),
+    (S::DefiningReal, &[C::LowerE], S::DefiningExponent),
+    (S::DefiningReal, &[C::Period], S::BadDot),
+    (S::DefiningExponent, &[C::Plus, C::Minus], S::DefiningExponentSign),
+    (S::DefiningExponent, DIGITS, S::DefiningExponentDigits),
+    (S::DefiningExponentSign, DIGITS, S::DefiningExponentDigits),
+    (S::DefiningExponentDigits, DIGITS, S::DefiningExponentDigits),
+    (S::DefiningExponentDigits, &[C::Underscore], S::DefiningExponentDigits),
+    (S::DefiningExponentDigits, ALL_LETTERS, S::BadIdentifier),
+    (S::DefiningExponentDigits, &[C::Period], S::BadDot),
+    // 0x, 0o and 0b literals; a digit from the wrong radix is an error
+    (S::HexPrefix, &[C::Underscore], S::HexPrefix),
+    (S::HexPrefix, HEX_DIGITS, S::HexDigits),
+    (S::HexPrefix, NON_HEX_LETTERS, S::BadDigit),
+    (S::HexPrefix, &[C::Period], S::BadDot),
+    (S::HexDigits, HEX_DIGITS, S::HexDigits),
+    (S::HexDigits, &[C::Underscore], S::HexDigits),
+    (S::HexDigits, NON_HEX_LETTERS, S::BadDigit),
+    (S::HexDigits, &[C::Period], S::HexDot),
+    (S::HexDot, &[C::Period], S::HexRange),
+    (S::OctalPrefix, &[C::Underscore], S::OctalPrefix),
+    (S::OctalPrefix, &[C::Zero, C::One, C::Octal], S::OctalDigits),
+    (S::OctalPrefix, &[C::Decimal], S::BadDigit),
+    (S::OctalPrefix, ALL_LETTERS, S::BadDigit),
+    (S::OctalPrefix, &[C::Period], S::BadDot),
+    (S::OctalDigits, &[C::Zero, C::One, C::Octal], S::OctalDigits),
+    (S::OctalDigits, &[C::Underscore], S::OctalDigits),
+    (S::OctalDigits, &[C::Decimal], S::BadDigit),
+    (S::OctalDigits, ALL_LETTERS, S::BadDigit),
+    (S::OctalDigits, &[C::Period], S::OctalDot),
+    (S::OctalDot, &[C::Period], S::OctalRange),
+    (S::BinaryPrefix, &[C::Underscore], S::BinaryPrefix),
+    (S::BinaryPrefix, &[C::Zero, C::One], S::BinaryDigits),
+    (S::BinaryPrefix, &[C::Octal, C::Decimal], S::BadDigit),
+    (S::BinaryPrefix, ALL_LETTERS, S::BadDigit),
+    (S::BinaryPrefix, &[C::Period], S::BadDot),
+    (S::BinaryDigits, &[C::Zero, C::One], S::BinaryDigits),
+    (S::BinaryDigits, &[C::Underscore], S::BinaryDigits),
+    (S::BinaryDigits, &[C::Octal, C::Decimal], S::BadDigit),
+    (S::BinaryDigits, ALL_LETTERS, S::BadDigit),
+    (S::BinaryDigits, &[C::Period], S::BinaryDot),
+    (S::BinaryDot, &[C::Period], S::BinaryRange),
+    // `.` means something different depending on the start condition
+    (S::Start, &[C::Period], S::Dot),
+    (S::StartMember, &[C::Period], S::MemberDot),
+    (S::StartLeadingDot, &[C::Period], S::LeadingDot),
+    (S::Dot, &[C::Period], S::Range),
+    (S::MemberDot, &[C::Period], S::Range),
+    (S::LeadingDot, &[C::Period], S::Range),
+    (S::LeadingDot, DIGITS, S::DefiningReal),
+    // Operators, longest first by virtue of maximal munch
+    (S::Start, &[C::Plus], S::Plus),
+    (S::Start, &[C::Minus], S::Minus),
+    (S::Start, &[C::Star], S::Star),
+    (S::Start, &[C::Slash], S::Slash),
+    (S::Start, &[C::Less], S::Lt),
+    (S::Lt, &[C::Equals], S::Le),
+    (S::Start, &[C::Greater], S::Gt),
+    (S::Gt, &[C::Equals], S::Ge),
+    (S::Start, &[C::Equals], S::Assign),
+    (S::Assign, &[C::Equals], S::EqEq),
+    (S::Assign, &[C::Greater], S::FatArrow),
+    (S::Start, &[C::Bang], S::Bang),
+    (S::Bang, &[C::Equals], S::NotEq),
+    // Separators
+    (S::Start, &[C::LParen], S::LParen),
+    (S::Start, &[C::RParen], S::RParen),
+    (S::Start, &[C::LBrace], S::LBrace),
+    (S::Start, &[C::RBrace], S::RBrace),
+    (S::Start, &[C::LBracket], S::LBracket),
+    (S::Start, &[C::RBracket], S::RBracket),
+    (S::Start, &[C::Comma], S::Comma),
+    (S::Start, &[C::Colon], S::Colon),
+    (S::Start, &[C::Semicolon], S::Semicolon),
+    (S::Start, &[C::Hash], S::Hash),
+    (S::Start, &[C::Dollar], S::Dollar),
+    (S::Dollar, &[C::Dollar], S::DoubleDollar),
+];
+
+/// The accepting states.
+pub const ACCEPTS: &[(States, Accept)] = &[
+    (S::Blank, Accept::Whitespace),
+    (S::DefiningIdentifier, Accept::Identifier),
+    (S::Zero, Accept::Integer(Radix::Decimal)),
+    (S::DefiningInteger, Accept::Integer(Radix::Decimal)),
+    (S::IntegerDot, Accept::Real),
+    (S::IntegerRange, Accept::IntegerBeforeRange(Radix::Decimal)),
+    (S::DefiningReal, Accept::Real),
+    (S::DefiningExponent, Accept::MissingExponent),
+    (S::DefiningExponentSign, Accept::MissingExponent),
+    (S::DefiningExponentDigits, Accept::Real),
+    (S::HexPrefix, Accept::MissingDigits),
+    (S::HexDigits, Accept::Integer(Radix::Hexadecimal)),
+    (S::HexDot, Accept::IllegalDot),
+    (S::HexRange, Accept::IntegerBeforeRange(Radix::Hexadecimal)),
+    (S::OctalPrefix, Accept::MissingDigits),
+    (S::OctalDigits, Accept::Integer(Radix::Octal)),
+    (S::OctalDot, Accept::IllegalDot),
+    (S::OctalRange, Accept::IntegerBeforeRange(Radix::Octal)),
+    (S::BinaryPrefix, Accept::MissingDigits),
+    (S::BinaryDigits, Accept::Integer(Radix::Binary)),
+    (S::BinaryDot, Accept::IllegalDot),
+    (S::BinaryRange, Accept::IntegerBeforeRange(Radix::Binary)),
+    (S::BadIdentifier, Accept::InvalidIdentifier),
+    (S::BadDigit, Accept::InvalidDigit),
+    (S::BadDot, Accept::IllegalDot),
+    (S::IllegalCharacter, Accept::IllegalCharacter),
+    (S::Dot, Accept::IllegalDot),
+    (S::MemberDot, Accept::Operator(Operator::Dot)),
+    (S::LeadingDot, Accept::IllegalDot),
+    (S::Range, Accept::Operator(Operator::Range)),
+    (S::Plus, Accept::Operator(Operator::Plus)),
+    (S::Minus, Accept::Operator(Operator::Minus)),
+    (S::Star, Accept::Operator(Operator::Star)),
+    (S::Slash, Accept::Operator(Operator::Slash)),
+    (S::Lt, Accept::Operator(Operator::Lt)),
+    (S::Le, Accept::Operator(Operator::Le)),
+    (S::Gt, Accept::Operator(Operator::Gt)),
+    (S::Ge, Accept::Operator(Operator::Ge)),
+    (S::Assign, Accept::Operator(Operator::Assign)),
+    (S::EqEq, Accept::Operator(Operator::EqEq)),
+    (S::FatArrow, Accept::Operator(Operator::FatArrow)),
+    (S::Bang, Accept::IllegalCharacter),
+    (S::NotEq, Accept::Operator(Operator::NotEq)),
+    (S::LParen, Accept::Separator(Separator::LParen)),
+    (S::RParen, Accept::Separator(Separator::RParen)),
+    (S::LBrace, Accept::Separator(Separator::LBrace)),
+    (S::RBrace, Accept::Separator(Separator::RBrace)),
+    (S::LBracket, Accept::Separator(Separator::LBracket)),
+    (S::RBracket, Accept::Separator(Separator::RBracket)),
+    (S::Comma, Accept::Separator(Separator::Comma)),
+    (S::Colon, Accept::Separator(Separator::Colon)),
+    (S::Semicolon, Accept::Separator(Separator::Semicolon)),
+    (S::Hash, Accept::Separator(Separator::Hash)),
+    (S::Dollar, Accept::IllegalCharacter),
+    (S::DoubleDollar, Accept::Separator(Separator::DoubleDollar)),
+];
+
+/// The lexer's automaton, built from [`TRANSITIONS`] and [`ACCEPTS`] on
+/// first use. State ids are the [`States`] discriminants and class ids the
+/// [`Class`] discriminants.
+pub fn builtin() -> &'static Dfa<Accept> {
+    static DFA: OnceLock<Dfa<Accept>> = OnceLock::new();
+    DFA.get_or_init(|| {
+        let mut classes = [0; 128];
+        for (c, class) in classes.iter_mut().enumerate() {
+            *class = Class::of_ascii(c as u8) as u8;
+        }
+        let mut dfa = Dfa::new(
+            classes,
+            Class::Other as u8,
+            NUM_CLASSES,
+            NUM_STATES,
+            States::Start as StateId,
+        );
+        for &(from, on, to) in TRANSITIONS {
+            let froms: &[States] = if from == States::Start {
+                &[States::Start, States::StartMember, States::StartLeadingDot]
+            } else {
+                &[from]
+            };
+            for &from in froms {
+                for &class in on {
+                    dfa.set_transition(from as StateId, class as u8, to as StateId);
+                }
+            }
+        }
+        for &(state, accept) in ACCEPTS {
+            dfa.set_accept(state as StateId, Some(accept));
+        }
+        dfa
+    })
+}
+
+/// The lexer's automaton as a Graphviz diagram, with states named after
+/// [`States`] and accepting states labelled with the token type they
+/// produce (or, for whitespace and errors, the [`Accept`]).
+pub fn dot() -> Dot<'static, Accept> {
+    let starts = [States::Start, States::StartMember, States::StartLeadingDot];
+    Dot::new(builtin())
+        .starts(&starts.map(|s| s as StateId))
+        .state_names(|s| format!("{:?}", States::ALL[s]))
+        .accept_labels(|a| match (a.token_type(), a) {
+            (Some(ty), Accept::Operator(op)) => format!("{} {}", ty, op.as_str()),
+            (Some(ty), Accept::Separator(sep)) => format!("{} {}", ty, sep.as_str()),
+            (Some(ty), _) => ty.to_string(),
+            (None, a) => format!("{:?}", a),
+        })
+        // Other is the catch-all, too long to spell out
+        .class_names(|c| match c {
+            c if c == Class::Other as u8 => Some(String::from("other")),
+            c if c == Class::UnicodeContinue as u8 => Some(String::from("XID_Continue")),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // What the automaton makes of the start of `s` from `state`, and how
+    // many bytes of it the token takes
+    fn run(state: States, s: &str) -> Option<(Accept, usize)> {
+        let dfa = builtin();
+        let (acc, len) = dfa.longest_match(state as StateId, s, |c| dfa.class_of(c))?;
+        Some((acc, len - acc.trailing()))
+    }
+
+    fn lex(s: &str) -> Option<(Accept, usize)> {
+        run(States::Start, s)
+    }
+
+    #[test]
+    fn radix_prefixes() {
+        assert_eq!(lex("0x1F;"), Some((Accept::Integer(Radix::Hexadecimal), 4)));
+        assert_eq!(lex("0o17 "), Some((Accept::Integer(Radix::Octal), 4)));
+        assert_eq!(lex("0b1010"), Some((Accept::Integer(Radix::Binary), 6)));
+        assert_eq!(lex("0x"), Some((Accept::MissingDigits, 2)));
+        assert_eq!(lex("0x;"), Some((Accept::MissingDigits, 2)));
+        assert_eq!(lex("0b"), Some((Accept::MissingDigits, 2)));
+        assert_eq!(lex("0b102"), Some((Accept::InvalidDigit, 5)));
+        assert_eq!(lex("0o8"), Some((Accept::InvalidDigit, 3)));
+    }
+
+    #[test]
+    fn decimals_and_reals() {
+        assert_eq!(lex("0"), Some((Accept::Integer(Radix::Decimal), 1)));
+        assert_eq!(lex("1_000;"), Some((Accept::Integer(Radix::Decimal), 5)));
+        assert_eq!(lex("22.00;"), Some((Accept::Real, 5)));
+        assert_eq!(lex("1e10"), Some((Accept::Real, 4)));
+        assert_eq!(lex("1.5E-3)"), Some((Accept::Real, 6)));
+        assert_eq!(lex("1e"), Some((Accept::MissingExponent, 2)));
+        assert_eq!(lex("1e+"), Some((Accept::MissingExponent, 3)));
+        assert_eq!(lex("1e+;"), Some((Accept::MissingExponent, 3)));
+        // The error ends at the first letter; recovery skips the rest
+        assert_eq!(lex("12ab"), Some((Accept::InvalidIdentifier, 3)));
+    }
+
+    #[test]
+    fn ranges() {
+        // The `..` is trailing context, left for the next token
+        assert_eq!(lex("1..5"), Some((Accept::IntegerBeforeRange(Radix::Decimal), 1)));
+        assert_eq!(lex("..5"), Some((Accept::Operator(Operator::Range), 2)));
+        assert_eq!(lex("1.5"), Some((Accept::Real, 3)));
+        // Any radix can start a range, though only decimals make reals
+        assert_eq!(lex("0x1F..0x2F"), Some((Accept::IntegerBeforeRange(Radix::Hexadecimal), 4)));
+        assert_eq!(lex("0o17..20"), Some((Accept::IntegerBeforeRange(Radix::Octal), 4)));
+        assert_eq!(lex("0b1_0.."), Some((Accept::IntegerBeforeRange(Radix::Binary), 5)));
+        assert_eq!(lex("0x1F.5"), Some((Accept::IllegalDot, 5)));
+        assert_eq!(lex("0b1."), Some((Accept::IllegalDot, 4)));
+        // With no digits there's nothing to range from
+        assert_eq!(lex("0x..5"), Some((Accept::IllegalDot, 3)));
+    }
+
+    #[test]
+    fn dots_depend_on_start_state() {
+        assert_eq!(lex(".x"), Some((Accept::IllegalDot, 1)));
+        assert_eq!(run(States::StartMember, ".x"), Some((Accept::Operator(Operator::Dot), 1)));
+        assert_eq!(run(States::StartLeadingDot, ".5"), Some((Accept::Real, 2)));
+        // Everything but `.` behaves the same from each start state
+        for state in [States::Start, States::StartMember, States::StartLeadingDot] {
+            assert_eq!(run(state, "x1"), Some((Accept::Identifier, 2)));
+            assert_eq!(run(state, "..2"), Some((Accept::Operator(Operator::Range), 2)));
+        }
+    }
+
+    #[test]
+    fn operators() {
+        assert_eq!(lex("<= "), Some((Accept::Operator(Operator::Le), 2)));
+        assert_eq!(lex("=="), Some((Accept::Operator(Operator::EqEq), 2)));
+        assert_eq!(lex("!="), Some((Accept::Operator(Operator::NotEq), 2)));
+        assert_eq!(lex("!x"), Some((Accept::IllegalCharacter, 1)));
+        assert_eq!(lex("$$"), Some((Accept::Separator(Separator::DoubleDollar), 2)));
+        assert_eq!(lex("@"), Some((Accept::IllegalCharacter, 1)));
+        assert_eq!(lex(" \t\n x"), Some((Accept::Whitespace, 4)));
+    }
+
+    #[test]
+    fn every_state_is_listed() {
+        for (i, s) in States::ALL.iter().enumerate() {
+            assert_eq!(*s as usize, i);
+        }
+        for (i, c) in Class::ALL.iter().enumerate() {
+            assert_eq!(*c as usize, i);
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use core::fmt;
+use std::borrow::Cow;
+
+/// Location of a token or error in the source.
+///
+/// `start` and `end` are byte offsets into the original input; `line` and
+/// `col` are 1-based and point at the first character of the span.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.col)
+    }
+}
+
+/// A token, borrowing its lexeme from the source where it can.
+///
+/// Lexemes are slices of the input except for Unicode identifiers that NFC
+/// normalization changed. Use [`Token::into_owned`] to keep a token past
+/// its source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token<'src> {
+    pub ty: TokenType,
+    pub lex: Cow<'src, str>,
+    pub span: Span,
+    /// The literal's value, for tokens whose lexeme needs decoding.
+    pub value: Option<Literal<'src>>,
+}
+
+/// A token that owns its text.
+pub type OwnedToken = Token<'static>;
+
+impl Token<'_> {
+    pub fn into_owned(self) -> OwnedToken {
+        Token {
+            ty: self.ty,
+            lex: Cow::Owned(self.lex.into_owned()),
+            span: self.span,
+            value: self.value.map(Literal::into_owned),
+        }
+    }
+}
+
+/// Decoded value of a literal token.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Literal<'src> {
+    /// A string literal with its escapes resolved. Borrowed from the
+    /// source unless it had escapes.
+    Str(Cow<'src, str>),
+    /// An integer literal, along with the base it was written in.
+    Int { value: u64, radix: Radix },
+    Real(f64),
+}
+
+impl Literal<'_> {
+    pub fn into_owned(self) -> Literal<'static> {
+        match self {
+            Literal::Str(s) => Literal::Str(Cow::Owned(s.into_owned())),
+            Literal::Int { value, radix } => Literal::Int { value, radix },
+            Literal::Real(x) => Literal::Real(x),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Radix {
+    /// `0b` prefix
+    Binary,
+    /// `0o` prefix
+    Octal,
+    Decimal,
+    /// `0x` prefix
+    Hexadecimal,
+}
+
+impl Radix {
+    pub fn value(&self) -> u32 {
+        match self {
+            Radix::Binary => 2,
+            Radix::Octal => 8,
+            Radix::Decimal => 10,
+            Radix::Hexadecimal => 16,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TokenType {
+    Identifier,
+    Number,
+    Real,
+    Separator(Separator),
+    Operator(Operator),
+    Keyword,
+    StringLiteral,
+    /// Only produced when the lexer is keeping comments.
+    Comment,
+    /// Text skipped while recovering from a lexical error.
+    Error,
+}
+
+impl TokenType {
+    /// The token's category, without any kind payload (`Operator`, not
+    /// `Operator(Le)`).
+    pub fn name(&self) -> &'static str {
+        match self {
+            TokenType::Identifier => "Identifier",
+            TokenType::Number => "Number",
+            TokenType::Real => "Real",
+            TokenType::Separator(_) => "Separator",
+            TokenType::Operator(_) => "Operator",
+            TokenType::Keyword => "Keyword",
+            TokenType::StringLiteral => "StringLiteral",
+            TokenType::Comment => "Comment",
+            TokenType::Error => "Error",
+        }
+    }
+}
+
+impl fmt::Display for TokenType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Separator {
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Comma,
+    Colon,
+    Semicolon,
+    Hash,
+    /// `$$`, which marks the sections of a program
+    DoubleDollar,
+}
+
+impl Separator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Separator::LParen => "(",
+            Separator::RParen => ")",
+            Separator::LBrace => "{",
+            Separator::RBrace => "}",
+            Separator::LBracket => "[",
+            Separator::RBracket => "]",
+            Separator::Comma => ",",
+            Separator::Colon => ":",
+            Separator::Semicolon => ";",
+            Separator::Hash => "#",
+            Separator::DoubleDollar => "$$",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Operator {
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    EqEq,
+    NotEq,
+    Assign,
+    FatArrow,
+    /// Member access, as in `a.b`
+    Dot,
+    /// `..`, as in `1..5`
+    Range,
+}
+
+impl Operator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Operator::Plus => "+",
+            Operator::Minus => "-",
+            Operator::Star => "*",
+            Operator::Slash => "/",
+            Operator::Lt => "<",
+            Operator::Gt => ">",
+            Operator::Le => "<=",
+            Operator::Ge => ">=",
+            Operator::EqEq => "==",
+            Operator::NotEq => "!=",
+            Operator::Assign => "=",
+            Operator::FatArrow => "=>",
+            Operator::Dot => ".",
+            Operator::Range => "..",
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use crate::table::{Class, States};
+use crate::{LexerError, OwnedToken, Span, TokenType};
+
+/// What the lexer did, step by step; see [`Lexer::trace`](crate::Lexer::trace).
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum TraceEvent {
+    /// The automaton moved from `state` to `next` on `c`. The move that
+    /// ends a token goes to [`States::Dead`]; its character is looked at
+    /// again as the start of the next token.
+    Step {
+        span: Span,
+        c: char,
+        state: States,
+        class: Class,
+        next: States,
+    },
+    /// A character of a string or comment. Those are scanned without the
+    /// automaton, so instead of states this has what's being scanned:
+    /// [`TokenType::StringLiteral`] or [`TokenType::Comment`].
+    Scan { span: Span, c: char, within: TokenType },
+    /// A comment was skipped. Comments the lexer keeps are tokens instead.
+    SkippedComment(Span),
+    /// A token was handed out.
+    Token(OwnedToken),
+    Error(LexerError),
+}